@@ -0,0 +1,110 @@
+// Timing comparison of the flat Grid<T> against the Vec<Vec<f64>> layout it replaced,
+// over the three passes the terrain goes through: generation, gradient and colouring.
+// Run with `cargo bench --bench grid_layout`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+#[allow(dead_code)]
+#[path = "../src/utils/grid.rs"]
+mod grid;
+
+use grid::Grid;
+
+const WIDTH: usize = 1280; // Same as WORLD_WIDTH
+const HEIGHT: usize = 720; // Same as WORLD_HEIGHT
+const RUNS: usize = 30;
+
+fn height_at(x: usize, y: usize) -> f64 {
+    0.5 + 0.25 * (x as f64 * 0.013).sin() * (y as f64 * 0.021).cos()
+}
+
+fn color_of(val: f64) -> [u8; 4] {
+    let v = (val.clamp(0.0, 1.0) * 255.0) as u8;
+    [v / 2, v, v, 255]
+}
+
+// The old layout: a Vec per column, indexed terrain[x][y]
+fn nested_generate() -> Vec<Vec<f64>> {
+    (0..WIDTH).map(|x| (0..HEIGHT).map(|y| height_at(x, y)).collect()).collect()
+}
+
+fn nested_gradient(terrain: &[Vec<f64>]) -> Vec<Vec<(f64, f64)>> {
+    (0..WIDTH)
+        .map(|x| {
+            (0..HEIGHT)
+                .map(|y| {
+                    let (x, y) = (x.clamp(1, WIDTH - 2), y.clamp(1, HEIGHT - 2));
+                    let dx = (terrain[x + 1][y] - terrain[x - 1][y]) / 2.0;
+                    let dy = (terrain[x][y + 1] - terrain[x][y - 1]) / 2.0;
+                    (-dx, -dy)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn nested_colors(terrain: &[Vec<f64>]) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(WIDTH * HEIGHT * 4);
+    for y in 0..HEIGHT {
+        for column in terrain.iter() {
+            pixels.extend_from_slice(&color_of(column[y]));
+        }
+    }
+    pixels
+}
+
+fn grid_generate() -> Grid<f64> {
+    Grid::from_fn(WIDTH, HEIGHT, height_at)
+}
+
+fn grid_gradient(terrain: &Grid<f64>) -> Grid<(f64, f64)> {
+    Grid::from_fn(WIDTH, HEIGHT, |x, y| {
+        let (x, y) = (x.clamp(1, WIDTH - 2), y.clamp(1, HEIGHT - 2));
+        let dx = (terrain[(x + 1, y)] - terrain[(x - 1, y)]) / 2.0;
+        let dy = (terrain[(x, y + 1)] - terrain[(x, y - 1)]) / 2.0;
+        (-dx, -dy)
+    })
+}
+
+fn grid_colors(terrain: &Grid<f64>) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(WIDTH * HEIGHT * 4);
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            pixels.extend_from_slice(&color_of(terrain[(x, y)]));
+        }
+    }
+    pixels
+}
+
+// Median of RUNS timings, so one slow run doesn't skew the result
+fn time<T>(mut f: impl FnMut() -> T) -> Duration {
+    let mut timings: Vec<Duration> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .collect();
+    timings.sort();
+    timings[RUNS / 2]
+}
+
+fn report(pass: &str, nested: Duration, flat: Duration) {
+    println!(
+        "{:<10} Vec<Vec> {:>8.3} ms   Grid {:>8.3} ms   {:.2}x",
+        pass,
+        nested.as_secs_f64() * 1000.0,
+        flat.as_secs_f64() * 1000.0,
+        nested.as_secs_f64() / flat.as_secs_f64()
+    );
+}
+
+fn main() {
+    println!("{}x{} terrain, median of {} runs", WIDTH, HEIGHT, RUNS);
+    let nested = nested_generate();
+    let flat = grid_generate();
+    report("generate", time(nested_generate), time(grid_generate));
+    report("gradient", time(|| nested_gradient(&nested)), time(|| grid_gradient(&flat)));
+    report("colors", time(|| nested_colors(&nested)), time(|| grid_colors(&flat)));
+}
@@ -12,13 +12,15 @@ mkdir -p videos
 # Get current datetime
 current_datetime=$(date +"%Y%m%d%H%M%S")
 
+# Frames are already thinned out by CAPTURE_EVERY_N_STEPS, so use them all
+frames_dir="${1:-frames}"
+
 # Create the video using FFmpeg
-ffmpeg -pattern_type glob -i '/media/volume/sdb/evolution_simulator/frames_filtered/frame_*.png' \
--framerate 60 \
+ffmpeg -framerate 60 -pattern_type glob -i "${frames_dir}/frame_*.png" \
 -c:v libx264 \
 -profile:v main \
 -level 4.0 \
 -preset veryslow \
 -crf 26 \
 -pix_fmt yuv420p \
-"/media/volume/sdb/evolution_simulator/videos/output_${current_datetime}.mp4"
+"videos/output_${current_datetime}.mp4"
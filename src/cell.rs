@@ -6,13 +6,28 @@ use rand_distr::{Distribution, Normal};
 use rayon::prelude::*;
 use std::time::{Duration, SystemTime};
 
-use crate::constants::{ENV_SEED, ENV_STEP, FULLSCREEN, HEIGHT, LOG_LEVEL, WIDTH, NUM_CELLS, TARGET_FRAME_RATE, FRAME_DUR, COLLIDE_SPRING, POST_REPRODUCTION_COLLIDE_SPRING, FRICTION_COEFF, PI};
-use crate::utils::ui_util::{hsva_to_rgba, rgba_to_hsva, UIContext, render_terrain};
+use crate::constants::{ENV_SEED, ENV_STEP, FULLSCREEN, HEIGHT, LOG_LEVEL, WIDTH, NUM_CELLS, TARGET_STEPS_PER_SECOND, PI};
+use crate::environment::Environment;
+use crate::obstacles::ObstacleMap;
+use crate::params::SimParams;
+use crate::utils::grid::Grid;
+use crate::utils::ui_util::{hsva_to_rgba, rgba_to_hsva, UIContext};
 use crate::utils::math_util::{velocity_to_polar, polar_to_velocity, gradient_along_heading, gradient_perpendicular_heading, generate_non_zero_integer, generate_random_position};
 
+// Heritable traits passed from parent to child, with mutation
+#[derive(Clone, Debug)]
+pub struct Genome {
+    pub membrane_color: [u8; 4],
+    pub inside_color: [u8; 4],
+    pub nucleus_color: [u8; 4],
+    pub reproduction_cost: f64,
+}
+
 pub struct Cell {
     pub id: i64,
     pub parent_id: i64,
+    pub lineage_id: i64, // id of the founding ancestor
+    pub generation: i64,
     pub creation_step: i64,
     pub age: i64,
     pub alive: bool,
@@ -63,7 +78,7 @@ pub struct Cell {
 }
 
 impl Cell {
-    pub fn new(id: i64, loop_step: i64) -> Self {
+    pub fn new(id: i64, loop_step: i64, world_width: f64, world_height: f64) -> Self {
         // Initialize a new cell
         let mut rng = rand::thread_rng();
         let mut mass: f64 = rng.gen_range(81.0..256.0);
@@ -85,14 +100,16 @@ impl Cell {
         Self {
             id,
             parent_id: -1,
+            lineage_id: id,
+            generation: 0,
             creation_step: loop_step,
             age: 0,
             alive: true,
             reproducing: false,
             reproduce_now: false,
             last_reproduction_age: 0,
-            x_pos: rng.gen_range((0.0 + radius)..(WIDTH as f64 - radius)),
-            y_pos: rng.gen_range((0.0 + radius)..(HEIGHT as f64 - radius)),
+            x_pos: rng.gen_range((0.0 + radius)..(world_width - radius)),
+            y_pos: rng.gen_range((0.0 + radius)..(world_height - radius)),
             x_vel,
             y_vel,
             x_acc: 0.0,
@@ -120,7 +137,7 @@ impl Cell {
         }
     }
 
-    pub fn new_from_reproduction(id: i64, parent_id: i64, creation_step: i64, mass: f64, x_pos: f64, y_pos: f64, x_vel: f64, y_vel: f64, membrane_color: [u8; 4], inside_color: [u8; 4], nucleus_color: [u8; 4], reproductive_cost: f64) -> Self {
+    pub fn new_from_reproduction(id: i64, parent_id: i64, creation_step: i64, mass: f64, x_pos: f64, y_pos: f64, x_vel: f64, y_vel: f64, membrane_color: [u8; 4], inside_color: [u8; 4], nucleus_color: [u8; 4], reproductive_cost: f64, generation: i64, lineage_id: i64) -> Self {
         let mut rng = rand::thread_rng();
         let color_mutate_magnitude = 0.04;
         let mut radius: f64 = (mass / PI).sqrt();
@@ -145,6 +162,8 @@ impl Cell {
         Self {
             id,
             parent_id,
+            lineage_id,
+            generation,
             creation_step,
             age: 0,
             alive: true,
@@ -180,21 +199,22 @@ impl Cell {
         }
     }
 
-    pub fn update(&mut self, terrain: &[Vec<(f64)>], gradient: &[Vec<(f64, f64)>], loop_step: i64) {
+    pub fn update(&mut self, env: &Environment, loop_step: i64) {
         trace!("cell::update >> Updating cell with id: {}, parent_id: {:?}, creation_time: {}, age: {}, x_pos: {}, y_pos: {}, x_vel: {}, y_vel: {}, mass: {}, radius: {}, inside_color: {}",
             self.id, self.parent_id, self.creation_step, self.age, self.x_pos, self.y_pos, self.x_vel, self.y_vel, self.mass, self.radius, self.inside_color[0]);
         self.update_age(loop_step);
-        self.update_velocity(gradient);
-        self.update_position();
-        self.handle_boundary_collision();
-        self.update_gravity_gradient_sense(gradient);
-        self.update_and_check_reproduction();
-        self.update_health();
-        self.update_energy();
-        if self.id == 1 {
-            //self.print_cell_properties();
+        self.update_velocity(env);
+        self.update_position(&env.params);
+        let (world_width, world_height) = (env.width() as f64, env.height() as f64);
+        self.handle_boundary_collision(world_width, world_height);
+        if let Some(obstacles) = env.obstacles.as_ref() {
+            self.handle_obstacle_collision(obstacles, world_width, world_height);
         }
-        self.update_light_exposure_sense(terrain);
+        self.update_gravity_gradient_sense(env);
+        self.update_and_check_reproduction();
+        self.update_health(&env.params);
+        self.update_energy(&env.params);
+        self.update_light_exposure_sense(env);
     }
     pub fn cell_freq(&mut self) -> f32{
         let base_frequency = 440.0;
@@ -226,14 +246,14 @@ impl Cell {
         }
 
     }
-    pub fn update_light_exposure_sense (&mut self, terrain: &[Vec<(f64)>]) {
-        self.light_exposure = terrain[self.x_pos.round() as usize][self.y_pos.round() as usize];
+    pub fn update_light_exposure_sense (&mut self, env: &Environment) {
+        self.light_exposure = env.sample_light_area(self.x_pos, self.y_pos, self.radius);
     }
 
-    pub fn update_energy(&mut self) {
-        self.energy -= self.energy_decay_rate * self.mass;
-        self.energy -= f64::min(self.health_restore_rate * self.health_capacity, self.health_capacity - self.health);
-        self.energy += self.light_exposure * self.light_consumtion_efficiency * 100.0;
+    pub fn update_energy(&mut self, params: &SimParams) {
+        self.energy -= self.energy_decay_rate * params.energy_decay_scale * self.mass;
+        self.energy -= f64::min(self.health_restore_rate * params.health_restore_scale * self.health_capacity, self.health_capacity - self.health);
+        self.energy += self.light_exposure * self.light_consumtion_efficiency * params.light_gain_scale * 100.0;
 
         if self.energy <= 0.0 {
             self.energy = 0.0;
@@ -244,10 +264,10 @@ impl Cell {
         
     }
 
-    pub fn update_health(&mut self) {
+    pub fn update_health(&mut self, params: &SimParams) {
 
-        self.health -= f64::min(self.health_decay_rate * self.health_capacity, self.health);
-        self.health += f64::min(f64::min(self.health_restore_rate * self.health_capacity, self.health_capacity - self.health), self.energy);
+        self.health -= f64::min(self.health_decay_rate * params.health_decay_scale * self.health_capacity, self.health);
+        self.health += f64::min(f64::min(self.health_restore_rate * params.health_restore_scale * self.health_capacity, self.health_capacity - self.health), self.energy);
 
         if self.health <= 0.0 {
             self.health = 0.0;
@@ -256,9 +276,28 @@ impl Cell {
             self.health = self.health_capacity;
         }
     }
+    pub fn genome(&self) -> Genome {
+        Genome {
+            membrane_color: self.membrane_color,
+            inside_color: self.inside_color,
+            nucleus_color: self.nucleus_color,
+            reproduction_cost: self.reproduction_cost,
+        }
+    }
+
+    // Takes on another cell's heritable traits, e.g. when spawning from a copied genome
+    pub fn apply_genome(&mut self, genome: &Genome) {
+        self.membrane_color = genome.membrane_color;
+        self.inside_color = genome.inside_color;
+        self.nucleus_color = genome.nucleus_color;
+        self.reproduction_cost = genome.reproduction_cost;
+    }
+
     pub fn print_cell_properties(&self) {
         println!("Cell Properties for ID {}:", self.id);
         println!("  Parent ID: {:?}", self.parent_id);
+        println!("  Lineage ID: {}", self.lineage_id);
+        println!("  Generation: {}", self.generation);
         println!("  Creation Step: {}", self.creation_step);
         println!("  Age: {}", self.age);
         println!("  Alive: {}", self.alive);
@@ -274,8 +313,9 @@ impl Cell {
         println!("  Energy Decay Rate: {:.3}", self.energy_decay_rate);
         println!("  Light Exposure: {:.3}", self.light_exposure);
         println!("  Light Consumption Efficiency: {:.3}", self.light_consumtion_efficiency);
-        println!("  Membrane Color: {:?}", self.energy_decay_rate);
+        println!("  Membrane Color: {:?}", self.membrane_color);
         println!("  Inside Color: {:?}", self.inside_color);
+        println!("  Nucleus Color: {:?}", self.nucleus_color);
         println!("  Gravity Gradient Along Heading: {:.6}", self.gravity_gradient_along_heading);
         println!("  Gravity Gradient Perpendicular Heading: {:.6}", self.gravity_gradient_perpendicular_heading);
         println!("  Reproducing: {}", self.reproducing);
@@ -285,15 +325,15 @@ impl Cell {
         println!();  
     }
 
-    pub fn update_gravity_gradient_sense(&mut self, gradient: &[Vec<(f64, f64)>]) {
-        let (g_x, g_y) = gradient[self.x_pos.round() as usize][self.y_pos.round() as usize];
+    pub fn update_gravity_gradient_sense(&mut self, env: &Environment) {
+        let (g_x, g_y) = env.sample_gradient(self.x_pos, self.y_pos);
         let gradient_along = gradient_along_heading((g_x, g_y), self.heading);
         let gradient_perpendicular = gradient_perpendicular_heading((g_x, g_y), self.heading);
         self.gravity_gradient_along_heading = gradient_along;
         self.gravity_gradient_perpendicular_heading = gradient_perpendicular;
     }
 
-    pub fn handle_cell_collision(&mut self, cell2: &mut Cell, terrain: &[Vec<(f64)>]) {
+    pub fn handle_cell_collision(&mut self, cell2: &mut Cell, terrain: &Grid<f64>, params: &SimParams) {
         let dx = self.x_pos - cell2.x_pos;
         let dy = self.y_pos - cell2.y_pos;
         let area_overlap: f64;
@@ -330,9 +370,9 @@ impl Cell {
             let mut force: f64;
             
             if (self.age - self.last_reproduction_age <= 30) && (cell2.age - cell2.last_reproduction_age <= 30) && ((self.id == cell2.parent_id) || (self.parent_id == cell2.id)) {
-                force = overlap * POST_REPRODUCTION_COLLIDE_SPRING;
+                force = overlap * params.post_reproduction_collide_spring;
             } else {
-                force = overlap * COLLIDE_SPRING;
+                force = overlap * params.collide_spring;
             }
 
             let ax1 = force / self.mass as f64;
@@ -353,9 +393,8 @@ impl Cell {
         }
     }
 
-    pub fn update_velocity(&mut self, gradient: &[Vec<(f64, f64)>]) {
-        // Assume self.x_pos and self.y_pos are usize for indexing into the gradient
-        let (dx, dy) = gradient[self.x_pos.round() as usize][self.y_pos.round() as usize];
+    pub fn update_velocity(&mut self, env: &Environment) {
+        let (dx, dy) = env.sample_gradient(self.x_pos, self.y_pos);
 
         //self.x_acc += dx;
         //self.y_acc += dy;
@@ -364,16 +403,23 @@ impl Cell {
         self.x_vel += dx;
         self.y_vel += dy;
 
+        // Drag toward the local current so cells drift with the water
+        if let Some(flow_field) = env.flow_field.as_ref() {
+            let (flow_x, flow_y) = flow_field.velocity_at(self.x_pos, self.y_pos);
+            self.x_vel += (flow_x - self.x_vel) * env.params.flow_drag;
+            self.y_vel += (flow_y - self.y_vel) * env.params.flow_drag;
+        }
+
         (self.heading, self.speed) = velocity_to_polar(self.x_vel, self.y_vel);
     
     }
     
-    pub fn update_position(&mut self) {
+    pub fn update_position(&mut self, params: &SimParams) {
         let mut rng = rand::thread_rng();
         let brownian_motion = 0.01;
 
-        self.x_vel *= (1.0 - FRICTION_COEFF);
-        self.y_vel *= (1.0 - FRICTION_COEFF);
+        self.x_vel *= (1.0 - params.friction_coeff);
+        self.y_vel *= (1.0 - params.friction_coeff);
 
         self.x_vel += rng.gen_range(-brownian_motion..brownian_motion);
         self.x_vel += rng.gen_range(-brownian_motion..brownian_motion);
@@ -391,10 +437,24 @@ impl Cell {
         self.age = loop_step - self.creation_step;
     }
 
-    pub fn handle_boundary_collision(&mut self) {
+    pub fn handle_obstacle_collision(&mut self, obstacles: &ObstacleMap, world_width: f64, world_height: f64) {
+        if let Some(((push_x, push_y), (nx, ny))) = obstacles.resolve_collision(self.x_pos, self.y_pos, self.radius) {
+            self.x_pos += push_x;
+            self.y_pos += push_y;
+            // Reflect the velocity component heading into the wall
+            let normal_vel = self.x_vel * nx + self.y_vel * ny;
+            if normal_vel < 0.0 {
+                self.x_vel -= 2.0 * normal_vel * nx;
+                self.y_vel -= 2.0 * normal_vel * ny;
+            }
+            self.handle_boundary_collision(world_width, world_height);
+        }
+    }
+
+    pub fn handle_boundary_collision(&mut self, world_width: f64, world_height: f64) {
         // Right boundary
-        if self.x_pos + self.radius >= WIDTH as f64 {
-            self.x_pos = WIDTH as f64 - self.radius;
+        if self.x_pos + self.radius >= world_width {
+            self.x_pos = world_width - self.radius;
             self.x_vel = -self.x_vel.abs();
         }
         // Left boundary
@@ -403,8 +463,8 @@ impl Cell {
             self.x_vel = self.x_vel.abs();
         }
         // Bottom boundary
-        if self.y_pos + self.radius >= HEIGHT as f64 {
-            self.y_pos = HEIGHT as f64 - self.radius;
+        if self.y_pos + self.radius >= world_height {
+            self.y_pos = world_height - self.radius;
             self.y_vel = -self.y_vel.abs();
         }
         // Top boundary
@@ -416,14 +476,18 @@ impl Cell {
 }
 
 // Function to update cells in parallel
-pub fn update_cells(cells: &mut Vec<Cell>, terrain: &[Vec<f64>], gradient: &[Vec<(f64, f64)>], loop_step: i64) -> Vec<f32> {
+// Returns the step's audio and how many children were born
+pub fn update_cells(cells: &mut Vec<Cell>, env: &Environment, loop_step: i64) -> (Vec<f32>, usize) {
     let mut num_cells_updated = 0;
     // let sample_rate = 44100;
     // let samples_per_frame = sample_rate / TARGET_FRAME_RATE;
     // let mut amplitude_sequence = vec![0.0; samples_per_frame as usize];
     // let mut amplitude_mult = 0.001;
 
+    let population_before = cells.len();
     let amplitude_sequence =reproduce_now(cells, loop_step);
+    // Counted here, before culling, so a child that dies on the step it was born still counts
+    let births = cells.len() - population_before;
     remove_dead_cells(cells);
     let len = cells.len();
     for i in 0..len {
@@ -431,10 +495,10 @@ pub fn update_cells(cells: &mut Vec<Cell>, terrain: &[Vec<f64>], gradient: &[Vec
             let (left, right) = cells.split_at_mut(i + 1);
             let cell1 = &mut left[i];
             let cell2 = &mut right[j - i - 1];
-            cell1.handle_cell_collision(cell2, terrain);        }
+            cell1.handle_cell_collision(cell2, &env.terrain, &env.params);        }
     }
     for cell in cells.iter_mut() {
-        cell.update(terrain, gradient, loop_step);
+        cell.update(env, loop_step);
         // if cell.id == 1 {
                 
         //     for i in 0..samples_per_frame {
@@ -453,15 +517,15 @@ pub fn update_cells(cells: &mut Vec<Cell>, terrain: &[Vec<f64>], gradient: &[Vec
         // cell.y_acc = 0.0;
         num_cells_updated += 1;
     }    
-    println!("Number of cells updated: {}", num_cells_updated);
-    println!();
+    trace!("cell::update_cells >> Number of cells updated: {}", num_cells_updated);
     //normalize_amplitude(&mut amplitude_sequence);
-    return amplitude_sequence;
+    return (amplitude_sequence, births);
 }
 
 pub fn reproduce_now(cells: &mut Vec<Cell>, loop_step: i64) -> Vec<f32> {
     let sample_rate = 44100;
-    let samples_per_frame = sample_rate / TARGET_FRAME_RATE;
+    // One step of audio lasts as long as one step at the target rate
+    let samples_per_frame = (sample_rate as f64 / TARGET_STEPS_PER_SECOND.max(1.0)) as u64;
     let mut amplitude_sequence = vec![0.0; samples_per_frame as usize];
     let mut amplitude_mult = 0.001;
 
@@ -482,7 +546,7 @@ pub fn reproduce_now(cells: &mut Vec<Cell>, loop_step: i64) -> Vec<f32> {
             let (x_offset, y_offset) = generate_random_position(&mut rng, cell.radius/2.0, cell.radius/2.0);
             let child_x_pos = cell.x_pos + x_offset;
             let child_y_pos = cell.y_pos + y_offset;
-            let child_cell = Cell::new_from_reproduction(max_id + 1 as i64, cell.id, loop_step, child_mass, child_x_pos, child_y_pos, cell.x_vel, cell.y_vel, cell.membrane_color, cell.inside_color, cell.nucleus_color, cell.reproduction_cost);
+            let child_cell = Cell::new_from_reproduction(max_id + 1 as i64, cell.id, loop_step, child_mass, child_x_pos, child_y_pos, cell.x_vel, cell.y_vel, cell.membrane_color, cell.inside_color, cell.nucleus_color, cell.reproduction_cost, cell.generation + 1, cell.lineage_id);
             //child_cell.print_cell_properties();
             cells_to_add.push(child_cell);
             max_id += 1;
@@ -1,16 +1,100 @@
 use log::{debug, error, info, trace, warn, LevelFilter};
 
 pub const LOG_LEVEL: LevelFilter = LevelFilter::Debug;
-pub const WIDTH: u32 = 1280;
+pub const WIDTH: u32 = 1280; // Window size, or output image size when headless
 pub const HEIGHT: u32 = 720;
+pub const WORLD_WIDTH: u32 = 1280; // Simulated world size in world units, independent of the window
+pub const WORLD_HEIGHT: u32 = 720;
 pub const FULLSCREEN: bool = false;
 pub const ENV_STEP: bool = false;
 pub const ENV_SEED: u32 = 0;
 pub const NUM_CELLS: usize = 200;
-pub const TARGET_FRAME_RATE: u64 = 1;
-pub const FRAME_DUR: u64 = 1_000 / TARGET_FRAME_RATE;
+pub const TARGET_STEPS_PER_SECOND: f64 = 1.0; // Simulation rate, 0 runs the simulation flat out
+pub const TARGET_RENDER_FPS: u64 = 60; // Rendered frames per second, independent of the simulation rate
 pub const COLLIDE_SPRING: f64 = -7.5;
 pub const POST_REPRODUCTION_COLLIDE_SPRING: f64 = -0.4;
 pub const FRICTION_COEFF: f64 = 0.075;
-pub const STEPS_PER_RENDER: i64 = 1;
 pub const PI : f64 = 3.14159265358;
+pub const FLOW_FIELD_ENABLED: bool = false;
+pub const FLOW_FREQUENCY: f64 = 0.003;
+pub const FLOW_STRENGTH: f64 = 0.5;
+pub const FLOW_TIME_RATE: f64 = 0.002;
+pub const FLOW_DRAG: f64 = 0.05;
+pub const FLOW_RENDER_STREAMLINES: bool = true;
+pub const FLOW_STREAMLINE_SPACING: u32 = 40;
+pub const OBSTACLE_LAYOUT: &str = "none"; // none, split, islands, maze, rocks
+pub const OBSTACLE_MASK_PATH: &str = ""; // PNG where dark pixels are walls, empty for none
+pub const OBSTACLES_BLOCK_LIGHT: bool = true;
+pub const LIGHT_DIRECTION: f64 = PI * 0.25; // Direction light travels in, radians
+pub const SHADOW_LENGTH: usize = 40;
+pub const SHADOW_LIGHT_FACTOR: f64 = 0.3;
+pub const TERRAIN_HEIGHTMAP_PATH: &str = ""; // Grayscale PNG to use as terrain, overrides TERRAIN_GENERATOR
+pub const TERRAIN_EXPORT_PATH: &str = ""; // Save the starting terrain as PNG, empty to skip
+pub const TERRAIN_GENERATOR: &str = "fbm_ridge"; // fbm_ridge, ramp, islands, voronoi, flat, rings
+pub const TERRAIN_FREQUENCY: f64 = 0.010; // Adjust this for smoother, wider valleys and ranges
+pub const TERRAIN_OCTAVES: i32 = 4;
+pub const TERRAIN_PERSISTENCE: f64 = 0.4; // Adjust this for smoother transitions
+pub const TERRAIN_LACUNARITY: f64 = 2.3; // Controls frequency increment between octaves
+pub const TERRAIN_VALLEY_FLOOR: f64 = -0.3; // This is the floor level for the valleys
+pub const TERRAIN_RIDGE_FREQUENCY: f64 = 0.004; // Frequency for the ridge or chasm lines
+pub const TERRAIN_RIDGE_MULTIPLIER: f64 = 0.5; // How much the ridges or chasms will influence the terrain
+pub const TERRAIN_RAMP_ANGLE: f64 = 0.0; // Radians, 0 rises to the right
+pub const TERRAIN_NUM_ISLANDS: usize = 6;
+pub const TERRAIN_ISLAND_RADIUS: f64 = 150.0;
+pub const TERRAIN_VORONOI_SITES: usize = 24;
+pub const TERRAIN_FLAT_HEIGHT: f64 = 0.5;
+pub const TERRAIN_RING_SPACING: f64 = 120.0;
+pub const TERRAIN_EROSION_ENABLED: bool = false;
+pub const TERRAIN_EROSION_DROPLETS: usize = 200_000;
+pub const TERRAIN_THERMAL_ITERATIONS: usize = 20;
+pub const TERRAIN_THERMAL_TALUS: f64 = 0.004; // Max height difference between neighbours before material slides
+pub const TERRAIN_SAMPLE_WRAP: bool = false; // Wrap terrain lookups at the edges instead of clamping
+pub const CAMERA_MIN_ZOOM: f64 = 0.1;
+pub const CAMERA_MAX_ZOOM: f64 = 32.0;
+pub const CAMERA_ZOOM_STEP: f64 = 1.2; // Zoom factor per mouse wheel notch
+pub const HUD_HISTORY_LEN: usize = 600; // Steps of history kept for the HUD charts
+pub const MAX_STEPS_PER_FRAME: u32 = 1024; // Cap on catch-up steps between two renders
+pub const MIN_STEPS_PER_SECOND: f64 = 0.25; // Slowest rate reachable with the speed keys
+pub const MAX_STEPS_PER_SECOND: f64 = 4096.0; // Speeding up past this runs the simulation flat out
+pub const FAST_FORWARD_RENDER_EVERY: u32 = 100; // In fast-forward only every Nth step is rendered
+pub const CAPTURE_EVERY_N_STEPS: i64 = 0; // Save a frame every N simulation steps, 0 disables capture
+pub const FRAME_CAPTURE_DIR: &str = "frames"; // Relative to the working directory
+pub const RECORD_VIDEO: bool = false; // Start recording a video as soon as the simulation starts, R toggles it
+pub const RECORD_OUTPUT_DIR: &str = "videos";
+pub const RECORD_EVERY_N_STEPS: i64 = 1; // Video frame stride in simulation steps
+pub const RECORD_WIDTH: u32 = 0; // Video resolution, 0 uses the rendered frame size
+pub const RECORD_HEIGHT: u32 = 0;
+pub const RECORD_FPS: u32 = 60; // Playback rate written into the video
+pub const RECORD_USE_FFMPEG: bool = true; // Pipe frames to ffmpeg for an mp4 when it is installed, else write Y4M
+pub const CAPTURE_WORKERS: usize = 2; // Background threads encoding captured PNGs
+pub const CAPTURE_QUEUE_LEN: usize = 8; // Frames waiting for a worker before capture blocks the main loop
+pub const CAPTURE_PNG_COMPRESSION: &str = "fast"; // fast, default, best, huffman, rle
+pub const FRONT_END: &str = "sdl"; // sdl opens a window, headless runs without one using the software renderer, tui draws in the terminal
+pub const HEADLESS_MAX_STEPS: i64 = 10_000; // Headless runs stop here, 0 runs until the population dies out
+pub const HEADLESS_LOG_EVERY_N_STEPS: i64 = 100;
+pub const TUI_RENDER_FPS: u64 = 10; // Terminal redraws per second, kept low for SSH sessions
+pub const TUI_PANEL_WIDTH: u16 = 30; // Stats panel columns, hidden on terminals narrower than twice this
+pub const TUI_TRUE_COLOR: bool = true; // 24-bit colour escapes, false falls back to the 256 colour palette
+pub const SVG_EXPORT_DIR: &str = "snapshots"; // S saves an SVG snapshot of the world here
+pub const SVG_TERRAIN_MODE: &str = "raster"; // raster, contours or none
+pub const SVG_CONTOUR_LEVELS: usize = 12;
+pub const SVG_CONTOUR_STEP: usize = 4; // Terrain grid cells per contour sample
+pub const SVG_ANNOTATE_CELLS: bool = false; // Label each cell with its id and lineage
+pub const SVG_LINEAGE_COLORS: bool = false; // Fill each cell with its lineage colour instead of its own
+pub const SVG_EXPORT_ON_EXIT: bool = false; // Headless runs save a final SVG snapshot
+pub const OVERLAY_BLOCK_SIZE: usize = 8; // World units per block of the terrain heatmaps
+pub const OVERLAY_ALPHA: u8 = 150;
+pub const OVERLAY_VELOCITY_SCALE: f64 = 10.0; // Velocity arrow length in world units per unit of speed
+pub const TRAIL_LENGTH: usize = 120; // Positions kept per cell for the trail layer, T toggles it
+pub const TRAIL_SAMPLE_EVERY: i64 = 2; // Steps between recorded trail positions
+pub const TRAILS_VISIBLE: bool = false; // Start with trails on, which also puts them in headless captures and videos
+pub const MINIMAP_VISIBLE: bool = true; // M toggles the minimap
+pub const MINIMAP_SIZE: u32 = 220; // Longest side of the minimap in pixels
+pub const MINIMAP_DENSITY_BLOCK: usize = 16; // World units per cell density block on the minimap
+pub const SIDE_PANEL_VISIBLE: bool = false; // G toggles the chart side panel
+pub const SIDE_PANEL_WIDTH: u32 = 260;
+pub const BRUSH_RADIUS: u32 = 40; // Spawn and kill brush radius in screen pixels
+pub const BRUSH_CELLS_PER_DAB: usize = 6; // Cells sprayed each time the brush touches down
+pub const TERRAIN_BRUSH_STRENGTH: f64 = 0.02; // Height change per dab at the brush centre
+pub const CONSOLE_HISTORY_LEN: usize = 200; // Lines of scrollback kept by the ` console
+pub const CONSOLE_STDIN: bool = true; // Also accept console commands typed into the terminal
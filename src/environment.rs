@@ -1,113 +1,323 @@
-use crate::cell::{update_cells, Cell};
+use crate::cell::{update_cells, Cell, Genome};
+use crate::flow_field::FlowField;
+use crate::obstacles::ObstacleMap;
+use crate::params::SimParams;
+use crate::terrain::{terrain_generator_from_config, erode_terrain_from_config, TerrainGenerator};
+use crate::utils::grid::Grid;
+use crate::utils::io_util::export_terrain_png;
 use log::{debug, error, info, trace, warn, LevelFilter};
 use noise::{NoiseFn, Perlin, Seedable};
 use rand::Rng;
 
-use crate::constants::{ENV_SEED, ENV_STEP, FULLSCREEN, HEIGHT, LOG_LEVEL, WIDTH, NUM_CELLS};
+use crate::constants::{ENV_SEED, ENV_STEP, FULLSCREEN, HEIGHT, LOG_LEVEL, WIDTH, NUM_CELLS, FLOW_FIELD_ENABLED, TERRAIN_EXPORT_PATH, TERRAIN_SAMPLE_WRAP};
+
+const MAX_PLACEMENT_ATTEMPTS: usize = 1000; // Random positions tried per starting cell before it is skipped
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TerrainBrushMode {
+    Raise,
+    Lower,
+    Smooth,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct StepStats {
+    pub step: i64,
+    pub births: usize,
+    pub deaths: usize,
+}
 
 pub struct Environment {
     pub cells: Vec<Cell>,
-    pub terrain: Vec<Vec<f64>>,
-    pub gradient: Vec<Vec<(f64, f64)>>,
+    pub terrain: Grid<f64>,
+    pub gradient: Grid<(f64, f64)>,
+    pub flow_field: Option<FlowField>,
+    pub obstacles: Option<ObstacleMap>,
+    pub terrain_generator: Box<dyn TerrainGenerator>,
+    pub terrain_version: u64, // Bumped whenever terrain or lighting changes so renderers can cache
+    pub step_stats: StepStats,
+    pub params: SimParams, // Live-tunable physics, see params.rs
 }
 
 impl Environment {
     pub fn new(width: u32, height: u32, env_seed: u32, loop_step: i64) -> Self {
         
-        let terrain: Vec<Vec<f64>> = generate_terrain(width as usize, height as usize, env_seed, loop_step);
-        let gradient: Vec<Vec<(f64, f64)>> = calculate_gradient(&terrain);
+        let terrain_generator = terrain_generator_from_config(env_seed);
+        debug!("Environment::new >> Generating terrain with {}", terrain_generator.name());
+        let mut terrain: Grid<f64> = terrain_generator.generate(width as usize, height as usize, loop_step);
+        erode_terrain_from_config(&mut terrain, env_seed);
+        if !TERRAIN_EXPORT_PATH.is_empty() {
+            export_terrain_png(&terrain, TERRAIN_EXPORT_PATH).unwrap_or_else(|e| {
+                error!("Environment::new >> Failed to export terrain to {}: {}", TERRAIN_EXPORT_PATH, e);
+            });
+        }
+        let gradient: Grid<(f64, f64)> = calculate_gradient(&terrain);
+        let obstacles = ObstacleMap::from_config(width as usize, height as usize, env_seed);
+        let mut rng = rand::thread_rng();
         let mut cells: Vec<Cell> = Vec::with_capacity(NUM_CELLS);
         for ii in 0..NUM_CELLS {
-            cells.push(Cell::new(ii as i64, loop_step));
-        }        Self { cells, terrain, gradient}
+            let mut cell = Cell::new(ii as i64, loop_step, width as f64, height as f64);
+            if let Some(obstacles) = obstacles.as_ref() {
+                // Don't start cells inside walls, and give up on cells that find no room
+                let mut attempts = 0;
+                while obstacles.resolve_collision(cell.x_pos, cell.y_pos, cell.radius).is_some() {
+                    attempts += 1;
+                    if attempts > MAX_PLACEMENT_ATTEMPTS {
+                        break;
+                    }
+                    cell.x_pos = rng.gen_range(cell.radius..(width as f64 - cell.radius));
+                    cell.y_pos = rng.gen_range(cell.radius..(height as f64 - cell.radius));
+                }
+                if attempts > MAX_PLACEMENT_ATTEMPTS {
+                    warn!("Environment::new >> No free space for cell {} after {} tries, skipping it", ii, MAX_PLACEMENT_ATTEMPTS);
+                    continue;
+                }
+            }
+            cells.push(cell);
+        }
+        let flow_field = if FLOW_FIELD_ENABLED {
+            Some(FlowField::new(env_seed, loop_step))
+        } else {
+            None
+        };
+        Self { cells, terrain, gradient, flow_field, obstacles, terrain_generator, terrain_version: 0, step_stats: StepStats::default(), params: SimParams::default() }
     }
 
     pub fn update(&mut self, loop_step: i64) -> Vec<f32> {
-        let amplitude_sequence = update_cells(&mut self.cells, &self.terrain, &self.gradient, loop_step);
+        if let Some(flow_field) = self.flow_field.as_mut() {
+            flow_field.update(loop_step);
+        }
+        // Take the cells out so they can read the rest of the environment while being updated
+        let mut cells = std::mem::take(&mut self.cells);
+        let population_before = cells.len();
+        let (amplitude_sequence, births) = update_cells(&mut cells, self, loop_step);
+        self.cells = cells;
+
+        let deaths = (population_before + births).saturating_sub(self.cells.len());
+        self.step_stats = StepStats { step: loop_step, births, deaths };
         return amplitude_sequence;
     }
-    pub fn update_terrain(&mut self, width: u32, height: u32, env_seed: u32, loop_step: i64) {
-        self.terrain = generate_terrain(width as usize, height as usize, env_seed, loop_step);
+    pub fn update_terrain(&mut self, env_seed: u32, loop_step: i64) {
+        self.terrain = self.terrain_generator.generate(self.width(), self.height(), loop_step);
+        erode_terrain_from_config(&mut self.terrain, env_seed);
+        self.gradient = calculate_gradient(&self.terrain);
+        self.terrain_version += 1;
+    }
+
+    pub fn width(&self) -> usize {
+        self.terrain.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.terrain.height()
     }
-}
 
+    // Topmost cell whose disc contains the point, cells later in the list are drawn on top
+    pub fn cell_at(&self, x: f64, y: f64) -> Option<&Cell> {
+        self.cells
+            .iter()
+            .rev()
+            .find(|cell| (cell.x_pos - x).powi(2) + (cell.y_pos - y).powi(2) <= cell.radius * cell.radius)
+    }
+
+    pub fn next_cell_id(&self) -> i64 {
+        self.cells.iter().map(|cell| cell.id).max().unwrap_or(0) + 1
+    }
 
-fn generate_terrain(width: usize, height: usize, env_seed: u32, loop_step: i64) -> Vec<Vec<f64>> {
-    let perlin = Perlin::new(env_seed);
-    let step_rate: f64 = 2.0;
-    let texture_frequency: f64 = 0.010; // Adjust this for smoother, wider valleys and ranges
-    let octaves: i32 = 4;
-    let persistence: f64 = 0.4; // Adjust this for smoother transitions
-    let lacunarity: f64 = 2.3; // Controls frequency increment between octaves
-
-    let valley_floor: f64 = -0.3; // This is the floor level for the valleys, adjust as needed
-    let smoothing_factor: f64 = 0.1; // This adjusts how quickly the value approaches the floor
-    let min_value: f64 = valley_floor + (-1.0 - valley_floor) * smoothing_factor * 1.1;
-
-    let ridge_frequency: f64 = 0.004; // Frequency for the ridge or chasm lines
-    let ridge_multiplier: f64 = 0.5; // How much the ridges or chasms will influence the terrain
-
-    let mut terrain: Vec<Vec<f64>> = vec![vec![0.0; height]; width];
-    trace!("Environment::generate_terrain >> Generating terrain");
-    for x in 0..width {
-        for y in 0..height {
-            let mut amplitude: f64 = 1.0;
-            let mut frequency = texture_frequency;
-            let mut total: f64 = 0.0;
-            let mut fbm_max_value = 0.0;
-            trace!("Environment::generate_terrain >> making octaves");
-            // Multi-octave Perlin noise (Fractal Brownian Motion)
-            for _ in 0..octaves {
-                total += perlin.get([
-                    x as f64 * frequency,
-                    y as f64 * frequency,
-                    loop_step as f64 * step_rate as f64 * frequency,
-                ]) * amplitude;
-                fbm_max_value += amplitude;
-                amplitude *= persistence;
-                frequency *= lacunarity;
+    // Adds a cell at rest at the given position, with a random genome unless one is given.
+    // Returns None when the spot is outside the world or inside an obstacle.
+    pub fn spawn_cell(&mut self, x: f64, y: f64, genome: Option<&Genome>, loop_step: i64) -> Option<i64> {
+        let id = self.next_cell_id();
+        let mut cell = Cell::new(id, loop_step, self.width() as f64, self.height() as f64);
+        if x < cell.radius || y < cell.radius || x > self.width() as f64 - cell.radius || y > self.height() as f64 - cell.radius {
+            return None;
+        }
+        if let Some(obstacles) = self.obstacles.as_ref() {
+            if obstacles.resolve_collision(x, y, cell.radius).is_some() {
+                return None;
             }
-            trace!("Environment::generate_terrain >> applying modifiers");
-            total /= fbm_max_value;
+        }
+        cell.x_pos = x;
+        cell.y_pos = y;
+        if let Some(genome) = genome {
+            cell.apply_genome(genome);
+        }
+        debug!("Environment::spawn_cell >> Spawned cell {} at ({:.1}, {:.1})", id, x, y);
+        self.cells.push(cell);
+        Some(id)
+    }
 
-            total += ridge_multiplier
-                * perlin.get([(x as f64) * ridge_frequency, (y as f64) * ridge_frequency]);
+    // Scatters up to count cells uniformly over a disc
+    pub fn spawn_cluster(&mut self, x: f64, y: f64, radius: f64, count: usize, genome: Option<&Genome>, loop_step: i64) -> Vec<i64> {
+        let mut rng = rand::thread_rng();
+        (0..count)
+            .filter_map(|_| {
+                let angle = rng.gen_range(0.0..std::f64::consts::TAU);
+                let dist = radius * rng.gen_range(0.0f64..1.0).sqrt();
+                self.spawn_cell(x + dist * angle.cos(), y + dist * angle.sin(), genome, loop_step)
+            })
+            .collect()
+    }
 
-            if total < valley_floor {
-                total = valley_floor + (total - valley_floor) * smoothing_factor;
+    pub fn kill_cell(&mut self, id: i64) -> bool {
+        let count = self.cells.len();
+        self.cells.retain(|cell| cell.id != id);
+        self.cells.len() < count
+    }
+
+    // Removes every cell whose centre is within the radius, returning how many were removed
+    pub fn kill_cells_in_radius(&mut self, x: f64, y: f64, radius: f64) -> usize {
+        let count = self.cells.len();
+        self.cells.retain(|cell| (cell.x_pos - x).powi(2) + (cell.y_pos - y).powi(2) > radius * radius);
+        count - self.cells.len()
+    }
+
+    // Raises, lowers or smooths the terrain under a disc with a smooth falloff toward the
+    // edge, then refreshes only the gradient around the painted area
+    pub fn paint_terrain(&mut self, x: f64, y: f64, radius: f64, strength: f64, mode: TerrainBrushMode) {
+        let (width, height) = (self.width(), self.height());
+        let x_min = (x - radius).floor().max(0.0) as usize;
+        let y_min = (y - radius).floor().max(0.0) as usize;
+        let x_max = ((x + radius).ceil().max(0.0) as usize).min(width - 1);
+        let y_max = ((y + radius).ceil().max(0.0) as usize).min(height - 1);
+        if x_min > x_max || y_min > y_max {
+            return;
+        }
+
+        // Smoothing reads from a copy of the area (plus a one pixel border) so the blur
+        // doesn't feed on its own output
+        let original = if mode == TerrainBrushMode::Smooth {
+            Some(Grid::from_fn(x_max - x_min + 3, y_max - y_min + 3, |px, py| {
+                *self.terrain.get_clamped(px as i64 + x_min as i64 - 1, py as i64 + y_min as i64 - 1)
+            }))
+        } else {
+            None
+        };
+        for yi in y_min..=y_max {
+            for xi in x_min..=x_max {
+                let dist = ((xi as f64 - x).powi(2) + (yi as f64 - y).powi(2)).sqrt();
+                if dist > radius {
+                    continue;
+                }
+                let falloff = 0.5 * (1.0 + (std::f64::consts::PI * dist / radius).cos());
+                let amount = strength * falloff;
+                let val = self.terrain[(xi, yi)];
+                let new_val = match mode {
+                    TerrainBrushMode::Raise => val + amount,
+                    TerrainBrushMode::Lower => val - amount,
+                    TerrainBrushMode::Smooth => {
+                        let original = original.as_ref().unwrap();
+                        let mut sum = 0.0;
+                        for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (0, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                            sum += original[(((xi - x_min) as i64 + 1 + dx) as usize, ((yi - y_min) as i64 + 1 + dy) as usize)];
+                        }
+                        // Strength is per dab, so scale it up to make smoothing as quick as raising
+                        val + (sum / 9.0 - val) * (amount * 10.0).min(1.0)
+                    }
+                };
+                self.terrain[(xi, yi)] = new_val.clamp(0.0, 1.0);
             }
-            trace!("Environment::generate_terrain >> normalizing and storing");
+        }
 
-            terrain[x][y] = (total - min_value) / (1.0 - min_value);
+        // Central differences reach one pixel past the painted area
+        for yi in y_min.saturating_sub(1)..=(y_max + 1).min(height - 1) {
+            for xi in x_min.saturating_sub(1)..=(x_max + 1).min(width - 1) {
+                self.gradient[(xi, yi)] = gradient_at(&self.terrain, xi, yi);
+            }
         }
+        self.terrain_version += 1;
     }
 
-    terrain
-}
+    // Places a cell somewhere else and stops it, kept inside the world
+    pub fn move_cell(&mut self, id: i64, x: f64, y: f64) -> bool {
+        let (width, height) = (self.width() as f64, self.height() as f64);
+        match self.cells.iter_mut().find(|cell| cell.id == id) {
+            Some(cell) => {
+                cell.x_pos = x.clamp(cell.radius, width - cell.radius);
+                cell.y_pos = y.clamp(cell.radius, height - cell.radius);
+                cell.x_vel = 0.0;
+                cell.y_vel = 0.0;
+                true
+            }
+            None => false,
+        }
+    }
 
-fn calculate_gradient(terrain: &Vec<Vec<f64>>) -> Vec<Vec<(f64, f64)>> {
-    let width = terrain.len();
-    let height = terrain[0].len();
-    let mut gradient = vec![vec![(0.0, 0.0); height]; width];
+    // Integer corners and fractional offsets for sampling at a world position. Clamping
+    // defers to the grid so terrain and gradient sample exactly like Grid::sample_bilinear.
+    fn sample_corners(&self, x: f64, y: f64) -> (usize, usize, usize, usize, f64, f64) {
+        if !TERRAIN_SAMPLE_WRAP {
+            return self.terrain.bilinear_corners(x, y);
+        }
+        let (width, height) = (self.width(), self.height());
+        let (x, y) = (x.rem_euclid(width as f64), y.rem_euclid(height as f64));
+        // NaN positions end up at the origin instead of panicking
+        let x0 = (x.floor() as usize).min(width - 1);
+        let y0 = (y.floor() as usize).min(height - 1);
+        let (x1, y1) = ((x0 + 1) % width, (y0 + 1) % height);
+        (x0, y0, x1, y1, x - x0 as f64, y - y0 as f64)
+    }
 
-    for x in 1..(width - 1) {
-        for y in 1..(height - 1) {
-            let dx = (terrain[x + 1][y] - terrain[x - 1][y]) / 2.0; // Change in x-direction
-            let dy = (terrain[x][y + 1] - terrain[x][y - 1]) / 2.0; // Change in y-direction
-            gradient[x][y] = (-1.0 * dx, -1.0 * dy);
+    pub fn sample_terrain(&self, x: f64, y: f64) -> f64 {
+        if !TERRAIN_SAMPLE_WRAP {
+            return self.terrain.sample_bilinear(x, y);
         }
+        let (x0, y0, x1, y1, fx, fy) = self.sample_corners(x, y);
+        let top = self.terrain[(x0, y0)] * (1.0 - fx) + self.terrain[(x1, y0)] * fx;
+        let bottom = self.terrain[(x0, y1)] * (1.0 - fx) + self.terrain[(x1, y1)] * fx;
+        top * (1.0 - fy) + bottom * fy
     }
 
-    // Handle edge cases (you could handle these more carefully if needed)
-    for x in 0..width {
-        gradient[x][0] = gradient[x][1];
-        gradient[x][height - 1] = gradient[x][height - 2];
+    pub fn sample_gradient(&self, x: f64, y: f64) -> (f64, f64) {
+        let (x0, y0, x1, y1, fx, fy) = self.sample_corners(x, y);
+        let lerp = |a: (f64, f64), b: (f64, f64), t: f64| (a.0 * (1.0 - t) + b.0 * t, a.1 * (1.0 - t) + b.1 * t);
+        let top = lerp(self.gradient[(x0, y0)], self.gradient[(x1, y0)], fx);
+        let bottom = lerp(self.gradient[(x0, y1)], self.gradient[(x1, y1)], fx);
+        lerp(top, bottom, fy)
     }
-    for y in 0..height {
-        gradient[0][y] = gradient[1][y];
-        gradient[width - 1][y] = gradient[width - 2][y];
+
+    // Mean terrain height over a disc, so large cells sense their whole footprint
+    pub fn sample_terrain_area(&self, x: f64, y: f64, radius: f64) -> f64 {
+        if radius < 1.0 {
+            return self.sample_terrain(x, y);
+        }
+        let mut total: f64 = 0.0;
+        let mut num_samples: usize = 0;
+        let r = radius.ceil() as i64;
+        for dx in -r..=r {
+            for dy in -r..=r {
+                if (dx * dx + dy * dy) as f64 <= radius * radius {
+                    total += self.sample_terrain(x + dx as f64, y + dy as f64);
+                    num_samples += 1;
+                }
+            }
+        }
+        total / num_samples as f64
     }
 
-    gradient
+    // Light reaching a disc: terrain height dimmed by any obstacle shadow at its centre
+    pub fn sample_light_area(&self, x: f64, y: f64, radius: f64) -> f64 {
+        let light = self.sample_terrain_area(x, y, radius);
+        match self.obstacles.as_ref() {
+            Some(obstacles) => light * obstacles.light_at(x, y),
+            None => light,
+        }
+    }
 }
 
+
+pub fn calculate_gradient(terrain: &Grid<f64>) -> Grid<(f64, f64)> {
+    terrain.par_map_xy(|x, y, _| gradient_at(terrain, x, y))
+}
+
+// Downhill slope at a grid point from central differences
+pub fn gradient_at(terrain: &Grid<f64>, x: usize, y: usize) -> (f64, f64) {
+    // Edges reuse the slope one pixel in, same as the interior neighbour. An axis under
+    // 3 cells has no central difference, so it is treated as flat.
+    let (width, height) = (terrain.width(), terrain.height());
+    let x = if width >= 3 { x.clamp(1, width - 2) } else { x };
+    let y = if height >= 3 { y.clamp(1, height - 2) } else { y };
+    let dx = if width >= 3 { (terrain[(x + 1, y)] - terrain[(x - 1, y)]) / 2.0 } else { 0.0 }; // Change in x-direction
+    let dy = if height >= 3 { (terrain[(x, y + 1)] - terrain[(x, y - 1)]) / 2.0 } else { 0.0 }; // Change in y-direction
+    (-1.0 * dx, -1.0 * dy)
+}
@@ -0,0 +1,66 @@
+use log::{debug, error, info, trace, warn, LevelFilter};
+use noise::{NoiseFn, Perlin};
+
+use crate::constants::{FLOW_FREQUENCY, FLOW_STRENGTH, FLOW_TIME_RATE};
+
+// Divergence-free current built from curl noise: the velocity is the curl of a
+// scalar Perlin potential, so the flow swirls without sources or sinks.
+pub struct FlowField {
+    perlin: Perlin,
+    pub frequency: f64,
+    pub strength: f64,
+    pub time_rate: f64,
+    pub time: f64,
+}
+
+impl FlowField {
+    pub fn new(env_seed: u32, loop_step: i64) -> Self {
+        // Offset the seed so the current does not line up with the terrain noise
+        let perlin = Perlin::new(env_seed.wrapping_add(1));
+        let mut flow_field = Self {
+            perlin,
+            frequency: FLOW_FREQUENCY,
+            strength: FLOW_STRENGTH,
+            time_rate: FLOW_TIME_RATE,
+            time: 0.0,
+        };
+        flow_field.update(loop_step);
+        flow_field
+    }
+
+    pub fn update(&mut self, loop_step: i64) {
+        self.time = loop_step as f64 * self.time_rate;
+    }
+
+    fn potential(&self, x: f64, y: f64) -> f64 {
+        self.perlin.get([x * self.frequency, y * self.frequency, self.time])
+    }
+
+    pub fn velocity_at(&self, x: f64, y: f64) -> (f64, f64) {
+        let eps: f64 = 1.0;
+        let d_dx = (self.potential(x + eps, y) - self.potential(x - eps, y)) / (2.0 * eps);
+        let d_dy = (self.potential(x, y + eps) - self.potential(x, y - eps)) / (2.0 * eps);
+
+        // Scale by 1 / frequency so the strength is independent of the noise scale
+        let scale = self.strength / self.frequency;
+        (d_dy * scale, -d_dx * scale)
+    }
+
+    pub fn streamline(&self, x_start: f64, y_start: f64, num_steps: usize, step_len: f64) -> Vec<(f64, f64)> {
+        let mut points: Vec<(f64, f64)> = Vec::with_capacity(num_steps + 1);
+        let (mut x, mut y) = (x_start, y_start);
+        points.push((x, y));
+        for _ in 0..num_steps {
+            let (u, v) = self.velocity_at(x, y);
+            let speed = (u * u + v * v).sqrt();
+            if speed < f64::EPSILON {
+                break;
+            }
+            x += u / speed * step_len;
+            y += v / speed * step_len;
+            points.push((x, y));
+        }
+        trace!("FlowField::streamline >> {} points from ({:.1}, {:.1})", points.len(), x_start, y_start);
+        points
+    }
+}
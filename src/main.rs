@@ -10,6 +10,7 @@ use std::time::{Duration, Instant, SystemTime};
 // External crate imports
 use env_logger::Builder;
 use log::{debug, error, info, trace, warn, LevelFilter};
+use image::RgbaImage;
 use rand::Rng;
 use sdl2::audio::{AudioCallback, AudioSpecDesired};
 
@@ -17,22 +18,38 @@ use sdl2::audio::{AudioCallback, AudioSpecDesired};
 mod cell;
 mod constants;
 mod environment;
+mod flow_field;
+mod obstacles;
+mod params;
+mod terrain;
 mod utils;
 
 // Functions from your internal modules
 use crate::utils::log_util::init_logging;
-use crate::utils::ui_util::{handle_events, init_sdl, render_current_state, capture_png, generate_loud_tone}; // Add this line
+use crate::utils::ui_util::{handle_events, init_sdl, render_current_state, render_hud_only, generate_loud_tone, TerrainTexture, UIState, read_canvas_rgba}; // Add this line
+use crate::utils::io_util::{png_compression_from_name, PngWriterPool};
+use crate::utils::raster_util::SoftwareRenderer;
+use crate::utils::ui_util::camera::Camera;
+use crate::utils::svg_util::export_svg_snapshot;
+use crate::utils::video_util::VideoRecorder;
+use crate::utils::console_util::StdinConsole;
+use crate::utils::tui_util::{KeyReader, Terminal, TuiKey, TuiView};
+use crate::utils::ui_util::controls::SimControl;
+use crate::utils::ui_util::hud::Hud;
+use crate::utils::ui_util::trails::Trails;
 use environment::Environment;
 
-use constants::{ENV_SEED, ENV_STEP, FULLSCREEN, HEIGHT, LOG_LEVEL, WIDTH, FRAME_DUR, TARGET_FRAME_RATE, NUM_CELLS, STEPS_PER_RENDER};
+use constants::{ENV_SEED, ENV_STEP, FULLSCREEN, HEIGHT, LOG_LEVEL, WIDTH, WORLD_WIDTH, WORLD_HEIGHT, NUM_CELLS, TARGET_RENDER_FPS, CAPTURE_EVERY_N_STEPS, FRAME_CAPTURE_DIR, CAPTURE_WORKERS, CAPTURE_QUEUE_LEN, CAPTURE_PNG_COMPRESSION, RECORD_OUTPUT_DIR, RECORD_EVERY_N_STEPS, RECORD_WIDTH, RECORD_HEIGHT, RECORD_FPS, RECORD_USE_FFMPEG, RECORD_VIDEO, FRONT_END, HEADLESS_MAX_STEPS, HEADLESS_LOG_EVERY_N_STEPS, SVG_EXPORT_ON_EXIT, CONSOLE_STDIN, TUI_RENDER_FPS};
+
+// Two seconds of mono f32 audio
+const MAX_QUEUED_AUDIO_BYTES: u32 = 44100 * 4 * 2;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let start_time = Instant::now();
-    let mut loop_step: i64 = 0;
     init_logging(start_time, LOG_LEVEL)?;
     info!(
-        "main >>  WIDTH: {}, HEIGHT: {}, FULLSCREEN: {}, ENV_STEP: {}, ENV_SEED: {}",
-        WIDTH, HEIGHT, FULLSCREEN, ENV_STEP, ENV_SEED
+        "main >>  WIDTH: {}, HEIGHT: {}, WORLD_WIDTH: {}, WORLD_HEIGHT: {}, FULLSCREEN: {}, ENV_STEP: {}, ENV_SEED: {}, FRONT_END: {}",
+        WIDTH, HEIGHT, WORLD_WIDTH, WORLD_HEIGHT, FULLSCREEN, ENV_STEP, ENV_SEED, FRONT_END
     );
     let env_seed = if ENV_SEED == 0 {
         let mut rng = rand::thread_rng();
@@ -40,8 +57,28 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     } else {
         ENV_SEED
     };
-    let mut should_render = true;
 
+    // The encoder threads only start when there are frames to capture
+    let png_writer = if CAPTURE_EVERY_N_STEPS > 0 && FRONT_END != "tui" {
+        std::fs::create_dir_all(FRAME_CAPTURE_DIR).unwrap_or_else(|e| {
+            error!("Failed to create frame capture directory {}: {}", FRAME_CAPTURE_DIR, e);
+        });
+        Some(PngWriterPool::new(CAPTURE_WORKERS, CAPTURE_QUEUE_LEN, png_compression_from_name(CAPTURE_PNG_COMPRESSION)))
+    } else {
+        None
+    };
+
+    match FRONT_END {
+        "headless" => run_headless(env_seed, png_writer.as_ref())?,
+        "tui" => run_tui(env_seed)?,
+        _ => run_sdl(env_seed, png_writer.as_ref())?,
+    }
+    debug!("main >> Exiting main loop");
+    Ok(())
+}
+
+fn run_sdl(env_seed: u32, png_writer: Option<&PngWriterPool>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut loop_step: i64 = 0;
     debug!("main >> init_sdl");
     let (mut ui_context, width, height) = init_sdl()?;
     let desired_spec = AudioSpecDesired {
@@ -53,59 +90,234 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     device.resume();
 
     debug!("main >> Environment::new. env_seed: {}", env_seed);
-    let mut env = Environment::new(width, height, env_seed, loop_step);
+    let mut env = Environment::new(WORLD_WIDTH, WORLD_HEIGHT, env_seed, loop_step);
+    let texture_creator = ui_context.canvas.texture_creator();
+    let mut terrain_texture = TerrainTexture::new(&texture_creator, env.width() as u32, env.height() as u32)?;
+    let mut ui_state = UIState::new(width, height, &env);
+    let stdin_console = if CONSOLE_STDIN { Some(StdinConsole::new()) } else { None };
 
     debug!("main >> Starting main loop");
 
+    let mut recorder: Option<VideoRecorder> = None;
+    let render_interval = Duration::from_secs_f64(1.0 / TARGET_RENDER_FPS.max(1) as f64);
     loop {
-        let loop_start_time = SystemTime::now();
-        loop_step += 1;
-        let (new_should_render, should_exit) = handle_events(&mut ui_context.event_pump, should_render);
-        should_render = new_should_render;
-        if should_exit {
+        let frame_start = Instant::now();
+        handle_events(&mut ui_context.event_pump, &mut ui_state, &mut env);
+        if let Some(stdin_console) = &stdin_console {
+            stdin_console.poll(&mut env);
+        }
+        if ui_state.should_exit {
             debug!("main >> Escape pressed or window closed, exiting");
             break;
         }
 
-        if should_render && loop_step % STEPS_PER_RENDER == 0 {
-            debug!("main >> render_current_state");
-            render_current_state(&mut env, &mut ui_context.canvas)?;
-            let filename = format!("/media/volume/sdb/evolution_simulator/frames/frame_{:06}.png", loop_step / STEPS_PER_RENDER);
-            capture_png(&ui_context.canvas, &filename).unwrap_or_else(|e| {
-                error!("Failed to capture PNG: {}", e);
-            });
+        let frame_deadline = frame_start + render_interval;
+        let step_budget = ui_state.sim_control.step_budget(frame_deadline);
+        let mut steps_this_frame = 0;
+        let mut capture_due = false;
+        let mut record_due = false;
+        while step_budget.allows(steps_this_frame) {
+            loop_step += 1;
+            steps_this_frame += 1;
+            debug!("main >> env.update()");
+            let mut amplitude_sequence = env.update(loop_step);
+            //amplitude_sequence = generate_loud_tone();
+            for (i, item) in amplitude_sequence.iter().enumerate() {
+                trace!("main >> t:{} A:{}", i, item);
+            }
+            // Above real time the audio can't keep up, so don't let the queue grow without bound
+            if device.size() < MAX_QUEUED_AUDIO_BYTES {
+                device.queue(&amplitude_sequence);
+            }
+            ui_state.hud.record(&env);
+            ui_state.trails.record(&env);
+
+            debug!("main >> env.update_terrain");
+            if ENV_STEP {
+                env.update_terrain(env_seed, loop_step);
+            }
+            // Stop here so the captured frame shows exactly this step
+            capture_due = ui_state.sim_control.capture_allowed() && every_n_steps(loop_step, CAPTURE_EVERY_N_STEPS);
+            record_due = ui_state.recording && every_n_steps(loop_step, RECORD_EVERY_N_STEPS);
+            if capture_due || record_due {
+                break;
+            }
+        }
+        ui_state.sim_control.finish_frame(&step_budget, steps_this_frame);
 
-        } else {
-            let canvas = &mut ui_context.canvas;
+        if !ui_state.recording && recorder.is_some() {
+            // Dropping the recorder flushes the file and waits for ffmpeg
+            recorder = None;
+        }
 
-            canvas.set_draw_color(sdl2::pixels::Color::RGB(255, 255, 255));
-            canvas.draw_line((10, 10), (10, 30)).unwrap();
-            canvas.draw_line((20, 10), (20, 30)).unwrap();
-            canvas.present();
+        if ui_state.should_render && (capture_due || record_due || ui_state.sim_control.render_due()) {
+            debug!("main >> render_current_state");
+            render_current_state(&mut env, &mut ui_state, &mut terrain_texture, &mut ui_context.canvas)?;
+            ui_state.sim_control.mark_rendered();
+            if capture_due || record_due {
+                match read_canvas_rgba(&ui_context.canvas) {
+                    Ok(frame) => {
+                        if record_due {
+                            if let Err(e) = record_frame(&mut recorder, &frame) {
+                                error!("Failed to record frame, stopping recording: {}", e);
+                                ui_state.recording = false;
+                            }
+                        }
+                        if let (true, Some(png_writer)) = (capture_due, png_writer) {
+                            let filename = format!("{}/frame_{:08}.png", FRAME_CAPTURE_DIR, loop_step);
+                            png_writer.submit(frame, filename).unwrap_or_else(|e| {
+                                error!("Failed to capture PNG: {}", e);
+                            });
+                        }
+                    }
+                    Err(e) => error!("Failed to read frame: {}", e),
+                }
+            }
+        } else if !ui_state.should_render {
+            render_hud_only(&ui_state, &mut ui_context.canvas)?;
         }
 
-        debug!("main >> env.update()");
-        let mut amplitude_sequence = env.update(loop_step);
-        //amplitude_sequence = generate_loud_tone();
-        for (i, item) in amplitude_sequence.iter().enumerate() {
-            println!("t:{} A:{}", i, item);
+        let elapsed_time = frame_start.elapsed();
+        if elapsed_time < render_interval {
+            sleep(render_interval - elapsed_time);
         }
-        device.queue(&amplitude_sequence);
+        debug!("main >> loop_step: {} steps_this_frame: {} elapsed_time: {}ms", loop_step, steps_this_frame, elapsed_time.as_millis())
+    }
 
-        debug!("main >> env.update_terrain");
+    Ok(())
+}
+
+// No window or audio: the simulation runs flat out and frames for captures and video
+// come from the software renderer
+fn run_headless(env_seed: u32, png_writer: Option<&PngWriterPool>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut loop_step: i64 = 0;
+    debug!("run_headless >> Environment::new. env_seed: {}", env_seed);
+    let mut env = Environment::new(WORLD_WIDTH, WORLD_HEIGHT, env_seed, loop_step);
+    let camera = Camera::new(WIDTH, HEIGHT, env.width(), env.height());
+    let mut renderer = SoftwareRenderer::new(WIDTH, HEIGHT);
+    let mut trails = Trails::new();
+    let mut recorder: Option<VideoRecorder> = None;
+    let mut recording = RECORD_VIDEO;
+    let stdin_console = if CONSOLE_STDIN { Some(StdinConsole::new()) } else { None };
+
+    while HEADLESS_MAX_STEPS == 0 || loop_step < HEADLESS_MAX_STEPS {
+        if let Some(stdin_console) = &stdin_console {
+            stdin_console.poll(&mut env);
+        }
+        loop_step += 1;
+        env.update(loop_step);
+        trails.record(&env);
         if ENV_STEP {
-            env.update_terrain(height, height, env_seed, loop_step);
+            env.update_terrain(env_seed, loop_step);
+        }
+
+        let capture_due = every_n_steps(loop_step, CAPTURE_EVERY_N_STEPS);
+        let record_due = recording && every_n_steps(loop_step, RECORD_EVERY_N_STEPS);
+        if capture_due || record_due {
+            let frame = renderer.render(&env, &camera, Some(&trails));
+            if record_due {
+                if let Err(e) = record_frame(&mut recorder, &frame) {
+                    error!("Failed to record frame, stopping recording: {}", e);
+                    recording = false;
+                    recorder = None;
+                }
+            }
+            if let (true, Some(png_writer)) = (capture_due, png_writer) {
+                let filename = format!("{}/frame_{:08}.png", FRAME_CAPTURE_DIR, loop_step);
+                png_writer.submit(frame, filename).unwrap_or_else(|e| {
+                    error!("Failed to capture PNG: {}", e);
+                });
+            }
         }
-        let elapsed_time = loop_start_time.elapsed()
-            .expect("Time went backwards")
-            .as_millis() as u64;
 
-        if elapsed_time < FRAME_DUR {
-            sleep(Duration::from_millis(FRAME_DUR - elapsed_time));
+        if every_n_steps(loop_step, HEADLESS_LOG_EVERY_N_STEPS) {
+            info!("run_headless >> step: {} population: {} births: {} deaths: {}", loop_step, env.cells.len(), env.step_stats.births, env.step_stats.deaths);
+        }
+        if env.cells.is_empty() {
+            info!("run_headless >> Population died out at step {}", loop_step);
+            break;
+        }
+    }
+    if SVG_EXPORT_ON_EXIT {
+        match export_svg_snapshot(&env) {
+            Ok(path) => info!("run_headless >> Saved SVG snapshot to {}", path),
+            Err(e) => error!("Failed to save SVG snapshot: {}", e),
         }
-        println!("loop_step: {} elapsed_time: {}ms fps: {}", loop_step, elapsed_time, 1000.0 / elapsed_time as f64)
     }
+    Ok(())
+}
 
-    debug!("main >> Exiting main loop");
+// Text-mode viewer for terminals and SSH sessions. Same pacing controls as the window,
+// but the keyboard owns stdin so there is no stdin console.
+fn run_tui(env_seed: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let mut loop_step: i64 = 0;
+    debug!("run_tui >> Environment::new. env_seed: {}", env_seed);
+    let mut env = Environment::new(WORLD_WIDTH, WORLD_HEIGHT, env_seed, loop_step);
+    let mut terminal = Terminal::enter()?;
+    let mut keys = KeyReader::new();
+    let mut view = TuiView::new(&env);
+    let mut sim_control = SimControl::new();
+    let mut hud = Hud::new();
+    let mut message = String::new();
+    let render_interval = Duration::from_secs_f64(1.0 / TUI_RENDER_FPS.max(1) as f64);
+
+    'frames: loop {
+        let frame_start = Instant::now();
+        for key in keys.poll() {
+            match key {
+                TuiKey::Quit => break 'frames,
+                TuiKey::TogglePause => sim_control.toggle_pause(),
+                TuiKey::Step => sim_control.request_step(),
+                TuiKey::SpeedUp => sim_control.speed_up(),
+                TuiKey::SlowDown => sim_control.slow_down(),
+                TuiKey::ResetSpeed => sim_control.reset_speed(),
+                TuiKey::FastForward => sim_control.toggle_fast_forward(),
+                TuiKey::Snapshot => {
+                    message = match export_svg_snapshot(&env) {
+                        Ok(path) => format!("saved {}", path),
+                        Err(e) => format!("snapshot failed: {}", e),
+                    }
+                }
+            }
+        }
+
+        let step_budget = sim_control.step_budget(frame_start + render_interval);
+        let mut steps_this_frame = 0;
+        while step_budget.allows(steps_this_frame) {
+            loop_step += 1;
+            steps_this_frame += 1;
+            env.update(loop_step);
+            hud.record(&env);
+            if ENV_STEP {
+                env.update_terrain(env_seed, loop_step);
+            }
+        }
+        sim_control.finish_frame(&step_budget, steps_this_frame);
+
+        if sim_control.render_due() {
+            let frame = view.render(terminal.size(), &env, &hud, &sim_control, &message);
+            terminal.draw(&frame)?;
+            sim_control.mark_rendered();
+        }
+
+        let elapsed_time = frame_start.elapsed();
+        if elapsed_time < render_interval {
+            sleep(render_interval - elapsed_time);
+        }
+    }
+    info!("run_tui >> Quit at step {} with {} cells", loop_step, env.cells.len());
     Ok(())
 }
+
+// True on every nth step. A stride of 0 or less turns the event off.
+fn every_n_steps(loop_step: i64, n: i64) -> bool {
+    n > 0 && loop_step % n == 0
+}
+
+// Starts the recorder on the first frame so it can take the frame size
+fn record_frame(recorder: &mut Option<VideoRecorder>, frame: &RgbaImage) -> Result<(), String> {
+    if recorder.is_none() {
+        *recorder = Some(VideoRecorder::new(RECORD_OUTPUT_DIR, RECORD_WIDTH, RECORD_HEIGHT, RECORD_FPS, RECORD_USE_FFMPEG, frame)?);
+    }
+    recorder.as_mut().unwrap().write_frame(frame)
+}
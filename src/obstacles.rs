@@ -0,0 +1,295 @@
+use image::imageops::FilterType;
+use log::{debug, error, info, trace, warn, LevelFilter};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::utils::grid::Grid;
+
+use crate::constants::{OBSTACLE_LAYOUT, OBSTACLE_MASK_PATH, OBSTACLES_BLOCK_LIGHT, LIGHT_DIRECTION, SHADOW_LENGTH, SHADOW_LIGHT_FACTOR};
+
+pub enum Obstacle {
+    Circle { x: f64, y: f64, radius: f64 },
+    Polygon { points: Vec<(f64, f64)> },
+}
+
+impl Obstacle {
+    pub fn rectangle(x_min: f64, y_min: f64, x_max: f64, y_max: f64) -> Self {
+        Obstacle::Polygon {
+            points: vec![(x_min, y_min), (x_max, y_min), (x_max, y_max), (x_min, y_max)],
+        }
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        match self {
+            Obstacle::Circle { x: cx, y: cy, radius } => (x - cx).powi(2) + (y - cy).powi(2) <= radius * radius,
+            Obstacle::Polygon { points } => point_in_polygon(points, x, y),
+        }
+    }
+
+    // Returns the push-out direction and penetration depth for a disc overlapping this obstacle
+    pub fn penetration(&self, x: f64, y: f64, radius: f64) -> Option<((f64, f64), f64)> {
+        match self {
+            Obstacle::Circle { x: cx, y: cy, radius: obstacle_radius } => {
+                let dx = x - cx;
+                let dy = y - cy;
+                let distance = (dx * dx + dy * dy).sqrt().max(0.001);
+                let overlap = radius + obstacle_radius - distance;
+                if overlap > 0.0 {
+                    Some(((dx / distance, dy / distance), overlap))
+                } else {
+                    None
+                }
+            }
+            Obstacle::Polygon { points } => {
+                let (closest_x, closest_y) = closest_point_on_polygon(points, x, y);
+                let dx = x - closest_x;
+                let dy = y - closest_y;
+                let distance = (dx * dx + dy * dy).sqrt().max(0.001);
+                if point_in_polygon(points, x, y) {
+                    // Centre is inside, push back out through the nearest edge
+                    Some(((-dx / distance, -dy / distance), distance + radius))
+                } else if distance < radius {
+                    Some(((dx / distance, dy / distance), radius - distance))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+pub struct ObstacleMap {
+    pub obstacles: Vec<Obstacle>,
+    pub solid: Grid<bool>,
+    pub light_mask: Grid<f64>,
+    mask_solid: Option<Grid<bool>>,
+    width: usize,
+    height: usize,
+}
+
+impl ObstacleMap {
+    pub fn new(obstacles: Vec<Obstacle>, mask_solid: Option<Grid<bool>>, width: usize, height: usize) -> Self {
+        debug!("ObstacleMap::new >> {} obstacles, mask: {}", obstacles.len(), mask_solid.is_some());
+        let solid: Grid<bool> = Grid::par_from_fn(width, height, |x, y| {
+            let from_mask = mask_solid.as_ref().map_or(false, |mask| mask[(x, y)]);
+            from_mask || obstacles.iter().any(|obstacle| obstacle.contains(x as f64, y as f64))
+        });
+        let light_mask = if OBSTACLES_BLOCK_LIGHT {
+            calculate_light_mask(&solid)
+        } else {
+            Grid::new(width, height, 1.0)
+        };
+        Self { obstacles, solid, light_mask, mask_solid, width, height }
+    }
+
+    pub fn from_config(width: usize, height: usize, env_seed: u32) -> Option<Self> {
+        let obstacles = layout_obstacles(OBSTACLE_LAYOUT, width as f64, height as f64, env_seed);
+        let mask_solid = if OBSTACLE_MASK_PATH.is_empty() {
+            None
+        } else {
+            match load_obstacle_mask(OBSTACLE_MASK_PATH, width, height) {
+                Ok(mask) => Some(mask),
+                Err(e) => {
+                    error!("ObstacleMap::from_config >> Failed to load obstacle mask {}: {}", OBSTACLE_MASK_PATH, e);
+                    None
+                }
+            }
+        };
+        if obstacles.is_empty() && mask_solid.is_none() {
+            return None;
+        }
+        Some(Self::new(obstacles, mask_solid, width, height))
+    }
+
+    pub fn is_solid(&self, x: f64, y: f64) -> bool {
+        if x < 0.0 || y < 0.0 {
+            return true;
+        }
+        let (xi, yi) = (x.round() as usize, y.round() as usize);
+        xi >= self.width || yi >= self.height || self.solid[(xi, yi)]
+    }
+
+    pub fn light_at(&self, x: f64, y: f64) -> f64 {
+        let xi = (x.round().max(0.0) as usize).min(self.width - 1);
+        let yi = (y.round().max(0.0) as usize).min(self.height - 1);
+        self.light_mask[(xi, yi)]
+    }
+
+    // Sum the push-out from every shape plus the image mask into one displacement and normal
+    pub fn resolve_collision(&self, x: f64, y: f64, radius: f64) -> Option<((f64, f64), (f64, f64))> {
+        let mut push_x: f64 = 0.0;
+        let mut push_y: f64 = 0.0;
+        for obstacle in self.obstacles.iter() {
+            if let Some(((nx, ny), depth)) = obstacle.penetration(x, y, radius) {
+                push_x += nx * depth;
+                push_y += ny * depth;
+            }
+        }
+        if let Some(((nx, ny), depth)) = self.mask_penetration(x, y, radius) {
+            push_x += nx * depth;
+            push_y += ny * depth;
+        }
+        let push_len = (push_x * push_x + push_y * push_y).sqrt();
+        if push_len < f64::EPSILON {
+            return None;
+        }
+        Some(((push_x, push_y), (push_x / push_len, push_y / push_len)))
+    }
+
+    fn mask_penetration(&self, x: f64, y: f64, radius: f64) -> Option<((f64, f64), f64)> {
+        let mask = self.mask_solid.as_ref()?;
+        let x_min = (x - radius).floor().max(0.0) as usize;
+        let y_min = (y - radius).floor().max(0.0) as usize;
+        let x_max = ((x + radius).ceil() as usize).min(self.width - 1);
+        let y_max = ((y + radius).ceil() as usize).min(self.height - 1);
+
+        let mut away_x: f64 = 0.0;
+        let mut away_y: f64 = 0.0;
+        let mut nearest: f64 = radius;
+        for xi in x_min..=x_max {
+            for yi in y_min..=y_max {
+                if !mask[(xi, yi)] {
+                    continue;
+                }
+                let dx = x - xi as f64;
+                let dy = y - yi as f64;
+                let distance = (dx * dx + dy * dy).sqrt();
+                if distance < radius {
+                    away_x += dx;
+                    away_y += dy;
+                    nearest = nearest.min(distance);
+                }
+            }
+        }
+        let away_len = (away_x * away_x + away_y * away_y).sqrt();
+        if nearest >= radius || away_len < f64::EPSILON {
+            return None;
+        }
+        Some(((away_x / away_len, away_y / away_len), radius - nearest))
+    }
+}
+
+fn calculate_light_mask(solid: &Grid<bool>) -> Grid<f64> {
+    let width = solid.width();
+    let height = solid.height();
+    // Walk from each pixel back toward the light; anything solid on the way casts a shadow
+    let (step_x, step_y) = (LIGHT_DIRECTION.cos(), LIGHT_DIRECTION.sin());
+    solid.par_map_xy(|x, y, is_solid| {
+        if *is_solid {
+            return 0.0;
+        }
+        for step in 1..=SHADOW_LENGTH {
+            let sx = x as f64 - step_x * step as f64;
+            let sy = y as f64 - step_y * step as f64;
+            if sx < 0.0 || sy < 0.0 || sx >= width as f64 || sy >= height as f64 {
+                break;
+            }
+            if solid[(sx as usize, sy as usize)] {
+                return SHADOW_LIGHT_FACTOR;
+            }
+        }
+        1.0
+    })
+}
+
+pub fn layout_obstacles(layout: &str, width: f64, height: f64, env_seed: u32) -> Vec<Obstacle> {
+    let wall: f64 = 12.0;
+    let gap: f64 = 80.0;
+    match layout {
+        "none" => Vec::new(),
+        "split" => {
+            // One wall down the middle with a single gap to connect the two habitats
+            let x_mid = width / 2.0;
+            vec![
+                Obstacle::rectangle(x_mid - wall / 2.0, 0.0, x_mid + wall / 2.0, height / 2.0 - gap / 2.0),
+                Obstacle::rectangle(x_mid - wall / 2.0, height / 2.0 + gap / 2.0, x_mid + wall / 2.0, height),
+            ]
+        }
+        "islands" => {
+            // Four chambers with no connection between them
+            let x_mid = width / 2.0;
+            let y_mid = height / 2.0;
+            vec![
+                Obstacle::rectangle(x_mid - wall / 2.0, 0.0, x_mid + wall / 2.0, height),
+                Obstacle::rectangle(0.0, y_mid - wall / 2.0, width, y_mid + wall / 2.0),
+            ]
+        }
+        "maze" => {
+            // Horizontal walls with the gap alternating sides
+            let num_walls = 4;
+            let spacing = height / (num_walls + 1) as f64;
+            (1..=num_walls)
+                .map(|i| {
+                    let y = spacing * i as f64;
+                    if i % 2 == 1 {
+                        Obstacle::rectangle(0.0, y - wall / 2.0, width - gap * 2.0, y + wall / 2.0)
+                    } else {
+                        Obstacle::rectangle(gap * 2.0, y - wall / 2.0, width, y + wall / 2.0)
+                    }
+                })
+                .collect()
+        }
+        "rocks" => {
+            let mut rng = StdRng::seed_from_u64(env_seed as u64);
+            (0..12)
+                .filter_map(|_| {
+                    // Small worlds get smaller rocks so each one still fits inside the walls
+                    let radius: f64 = rng.gen_range(20.0..70.0_f64).min(width.min(height) / 4.0);
+                    if radius < 1.0 {
+                        return None;
+                    }
+                    Some(Obstacle::Circle {
+                        x: rng.gen_range(radius..(width - radius)),
+                        y: rng.gen_range(radius..(height - radius)),
+                        radius,
+                    })
+                })
+                .collect()
+        }
+        _ => {
+            warn!("obstacles::layout_obstacles >> Unknown obstacle layout: {}", layout);
+            Vec::new()
+        }
+    }
+}
+
+// Dark pixels in the image are solid, it is stretched to cover the whole world
+pub fn load_obstacle_mask(path: &str, width: usize, height: usize) -> Result<Grid<bool>, String> {
+    let img = image::open(path).map_err(|e| e.to_string())?.to_luma8();
+    let img = image::imageops::resize(&img, width as u32, height as u32, FilterType::Nearest);
+    let values: Vec<bool> = img.into_raw().into_iter().map(|val| val < 128).collect();
+    Grid::from_vec(width, height, values)
+}
+
+fn point_in_polygon(points: &[(f64, f64)], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    let mut j = points.len() - 1;
+    for i in 0..points.len() {
+        let (xi, yi) = points[i];
+        let (xj, yj) = points[j];
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+fn closest_point_on_polygon(points: &[(f64, f64)], x: f64, y: f64) -> (f64, f64) {
+    let mut closest = points[0];
+    let mut closest_dist_sq = f64::MAX;
+    for i in 0..points.len() {
+        let (ax, ay) = points[i];
+        let (bx, by) = points[(i + 1) % points.len()];
+        let (ex, ey) = (bx - ax, by - ay);
+        let edge_len_sq = (ex * ex + ey * ey).max(f64::EPSILON);
+        let t = (((x - ax) * ex + (y - ay) * ey) / edge_len_sq).clamp(0.0, 1.0);
+        let (px, py) = (ax + ex * t, ay + ey * t);
+        let dist_sq = (x - px).powi(2) + (y - py).powi(2);
+        if dist_sq < closest_dist_sq {
+            closest_dist_sq = dist_sq;
+            closest = (px, py);
+        }
+    }
+    closest
+}
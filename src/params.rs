@@ -0,0 +1,133 @@
+use log::{debug, error, info, trace, warn, LevelFilter};
+
+use crate::constants::{COLLIDE_SPRING, POST_REPRODUCTION_COLLIDE_SPRING, FRICTION_COEFF, FLOW_DRAG};
+
+// Physics and metabolism knobs that can be changed while the simulation runs.
+// They start from the constants and live on the Environment so every cell sees the same values.
+#[derive(Clone, Debug)]
+pub struct SimParams {
+    pub friction_coeff: f64,
+    pub collide_spring: f64,
+    pub post_reproduction_collide_spring: f64,
+    pub flow_drag: f64,
+    // Multipliers on each cell's own rates, so cells keep their individual differences
+    pub health_decay_scale: f64,
+    pub energy_decay_scale: f64,
+    pub health_restore_scale: f64,
+    pub light_gain_scale: f64,
+}
+
+impl Default for SimParams {
+    fn default() -> Self {
+        Self {
+            friction_coeff: FRICTION_COEFF,
+            collide_spring: COLLIDE_SPRING,
+            post_reproduction_collide_spring: POST_REPRODUCTION_COLLIDE_SPRING,
+            flow_drag: FLOW_DRAG,
+            health_decay_scale: 1.0,
+            energy_decay_scale: 1.0,
+            health_restore_scale: 1.0,
+            light_gain_scale: 1.0,
+        }
+    }
+}
+
+pub struct ParamSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub min: f64,
+    pub max: f64,
+    pub get: fn(&SimParams) -> f64,
+    pub set: fn(&mut SimParams, f64),
+}
+
+// Every parameter the console may read or write, with the range a value must fall in
+pub static PARAM_REGISTRY: [ParamSpec; 8] = [
+    ParamSpec {
+        name: "friction",
+        description: "fraction of velocity lost each step",
+        min: 0.0,
+        max: 1.0,
+        get: |params| params.friction_coeff,
+        set: |params, val| params.friction_coeff = val,
+    },
+    ParamSpec {
+        name: "collide_spring",
+        description: "spring constant pushing overlapping cells apart (negative)",
+        min: -100.0,
+        max: 0.0,
+        get: |params| params.collide_spring,
+        set: |params, val| params.collide_spring = val,
+    },
+    ParamSpec {
+        name: "post_reproduction_spring",
+        description: "spring constant between a parent and child just after division",
+        min: -100.0,
+        max: 0.0,
+        get: |params| params.post_reproduction_collide_spring,
+        set: |params, val| params.post_reproduction_collide_spring = val,
+    },
+    ParamSpec {
+        name: "flow_drag",
+        description: "how strongly cells are pulled along by the flow field",
+        min: 0.0,
+        max: 1.0,
+        get: |params| params.flow_drag,
+        set: |params, val| params.flow_drag = val,
+    },
+    ParamSpec {
+        name: "health_decay",
+        description: "multiplier on every cell's health decay rate",
+        min: 0.0,
+        max: 100.0,
+        get: |params| params.health_decay_scale,
+        set: |params, val| params.health_decay_scale = val,
+    },
+    ParamSpec {
+        name: "energy_decay",
+        description: "multiplier on every cell's energy decay rate",
+        min: 0.0,
+        max: 100.0,
+        get: |params| params.energy_decay_scale,
+        set: |params, val| params.energy_decay_scale = val,
+    },
+    ParamSpec {
+        name: "health_restore",
+        description: "multiplier on every cell's health restore rate",
+        min: 0.0,
+        max: 100.0,
+        get: |params| params.health_restore_scale,
+        set: |params, val| params.health_restore_scale = val,
+    },
+    ParamSpec {
+        name: "light_gain",
+        description: "multiplier on the energy cells get from light",
+        min: 0.0,
+        max: 100.0,
+        get: |params| params.light_gain_scale,
+        set: |params, val| params.light_gain_scale = val,
+    },
+];
+
+pub fn find_param(name: &str) -> Result<&'static ParamSpec, String> {
+    PARAM_REGISTRY
+        .iter()
+        .find(|spec| spec.name == name)
+        .ok_or_else(|| format!("Unknown parameter '{}', try 'params'", name))
+}
+
+impl SimParams {
+    pub fn get(&self, name: &str) -> Result<f64, String> {
+        Ok((find_param(name)?.get)(self))
+    }
+
+    pub fn set(&mut self, name: &str, val: f64) -> Result<(), String> {
+        let spec = find_param(name)?;
+        if !val.is_finite() || val < spec.min || val > spec.max {
+            return Err(format!("{} must be between {} and {}", spec.name, spec.min, spec.max));
+        }
+        (spec.set)(self, val);
+        debug!("SimParams::set >> {} = {}", name, val);
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+use log::{debug, error, info, trace, warn, LevelFilter};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::utils::grid::Grid;
+
+pub struct ErosionParams {
+    pub num_droplets: usize,
+    pub max_lifetime: usize,
+    pub inertia: f64, // How much a droplet keeps its direction instead of following the slope
+    pub sediment_capacity_factor: f64,
+    pub min_sediment_capacity: f64,
+    pub erode_speed: f64,
+    pub deposit_speed: f64,
+    pub evaporate_speed: f64,
+    pub gravity: f64,
+    pub erosion_radius: i64,
+}
+
+// Simulates rain droplets running downhill, picking up sediment on steep slopes and
+// dropping it where they slow down, which carves valleys and fills basins
+pub fn hydraulic_erosion(terrain: &mut Grid<f64>, params: &ErosionParams, env_seed: u32) {
+    let width = terrain.width();
+    let height = terrain.height();
+    if width < 3 || height < 3 {
+        return;
+    }
+    debug!("erosion::hydraulic_erosion >> {} droplets on {}x{}", params.num_droplets, width, height);
+    let mut rng = StdRng::seed_from_u64(env_seed as u64);
+    let brush = erosion_brush(params.erosion_radius);
+
+    for _ in 0..params.num_droplets {
+        let mut x: f64 = rng.gen_range(0.0..(width - 1) as f64);
+        let mut y: f64 = rng.gen_range(0.0..(height - 1) as f64);
+        let mut dir_x: f64 = 0.0;
+        let mut dir_y: f64 = 0.0;
+        let mut speed: f64 = 1.0;
+        let mut water: f64 = 1.0;
+        let mut sediment: f64 = 0.0;
+
+        for _ in 0..params.max_lifetime {
+            let (node_x, node_y) = (x as usize, y as usize);
+            let (old_height, grad_x, grad_y) = height_and_gradient(terrain, x, y);
+
+            dir_x = dir_x * params.inertia - grad_x * (1.0 - params.inertia);
+            dir_y = dir_y * params.inertia - grad_y * (1.0 - params.inertia);
+            let dir_len = (dir_x * dir_x + dir_y * dir_y).sqrt();
+            if dir_len < f64::EPSILON {
+                break;
+            }
+            dir_x /= dir_len;
+            dir_y /= dir_len;
+            x += dir_x;
+            y += dir_y;
+
+            if x < 0.0 || y < 0.0 || x >= (width - 1) as f64 || y >= (height - 1) as f64 {
+                break;
+            }
+
+            let (new_height, _, _) = height_and_gradient(terrain, x, y);
+            let delta_height = new_height - old_height;
+            let capacity = f64::max(-delta_height * speed * water * params.sediment_capacity_factor, params.min_sediment_capacity);
+
+            if sediment > capacity || delta_height > 0.0 {
+                // Uphill fills the pit it came from, otherwise drop the excess
+                let deposit = if delta_height > 0.0 {
+                    f64::min(delta_height, sediment)
+                } else {
+                    (sediment - capacity) * params.deposit_speed
+                };
+                sediment -= deposit;
+                deposit_bilinear(terrain, node_x, node_y, x - dir_x - node_x as f64, y - dir_y - node_y as f64, deposit);
+            } else {
+                let erode = f64::min((capacity - sediment) * params.erode_speed, -delta_height);
+                for (bx, by, weight) in brush.iter() {
+                    let ex = node_x as i64 + bx;
+                    let ey = node_y as i64 + by;
+                    if ex < 0 || ey < 0 || ex >= width as i64 || ey >= height as i64 {
+                        continue;
+                    }
+                    let cell = &mut terrain[(ex as usize, ey as usize)];
+                    let removed = f64::min(*cell, erode * weight);
+                    *cell -= removed;
+                    sediment += removed;
+                }
+            }
+
+            speed = (speed * speed - delta_height * params.gravity).max(0.0).sqrt();
+            water *= 1.0 - params.evaporate_speed;
+        }
+    }
+}
+
+// Moves material from any slope steeper than the talus angle down to its neighbours
+pub fn thermal_weathering(terrain: &mut Grid<f64>, iterations: usize, talus: f64, rate: f64) {
+    let width = terrain.width();
+    let height = terrain.height();
+    debug!("erosion::thermal_weathering >> {} iterations, talus: {}", iterations, talus);
+    let neighbours: [(i64, i64); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+    let mut delta: Grid<f64> = Grid::new(width, height, 0.0);
+
+    for _ in 0..iterations {
+        delta.iter_mut().for_each(|val| *val = 0.0);
+        for x in 0..width {
+            for y in 0..height {
+                for (nx, ny) in neighbours.iter() {
+                    let (tx, ty) = (x as i64 + nx, y as i64 + ny);
+                    if tx < 0 || ty < 0 || tx >= width as i64 || ty >= height as i64 {
+                        continue;
+                    }
+                    let diff = terrain[(x, y)] - terrain[(tx as usize, ty as usize)];
+                    if diff > talus {
+                        let moved = (diff - talus) * rate * 0.25;
+                        delta[(x, y)] -= moved;
+                        delta[(tx as usize, ty as usize)] += moved;
+                    }
+                }
+            }
+        }
+        for (val, change) in terrain.iter_mut().zip(delta.iter()) {
+            *val += change;
+        }
+    }
+}
+
+fn height_and_gradient(terrain: &Grid<f64>, x: f64, y: f64) -> (f64, f64, f64) {
+    let (node_x, node_y) = (x as usize, y as usize);
+    let (u, v) = (x - node_x as f64, y - node_y as f64);
+    let h00 = terrain[(node_x, node_y)];
+    let h10 = terrain[(node_x + 1, node_y)];
+    let h01 = terrain[(node_x, node_y + 1)];
+    let h11 = terrain[(node_x + 1, node_y + 1)];
+
+    let grad_x = (h10 - h00) * (1.0 - v) + (h11 - h01) * v;
+    let grad_y = (h01 - h00) * (1.0 - u) + (h11 - h10) * u;
+    let height = h00 * (1.0 - u) * (1.0 - v) + h10 * u * (1.0 - v) + h01 * (1.0 - u) * v + h11 * u * v;
+    (height, grad_x, grad_y)
+}
+
+fn deposit_bilinear(terrain: &mut Grid<f64>, node_x: usize, node_y: usize, u: f64, v: f64, amount: f64) {
+    let (u, v) = (u.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+    terrain[(node_x, node_y)] += amount * (1.0 - u) * (1.0 - v);
+    terrain[(node_x + 1, node_y)] += amount * u * (1.0 - v);
+    terrain[(node_x, node_y + 1)] += amount * (1.0 - u) * v;
+    terrain[(node_x + 1, node_y + 1)] += amount * u * v;
+}
+
+// Offsets and normalized weights for spreading erosion over a disc
+fn erosion_brush(radius: i64) -> Vec<(i64, i64, f64)> {
+    let mut brush: Vec<(i64, i64, f64)> = Vec::new();
+    for bx in -radius..=radius {
+        for by in -radius..=radius {
+            let dist = ((bx * bx + by * by) as f64).sqrt();
+            if dist <= radius as f64 {
+                brush.push((bx, by, 1.0 - dist / (radius as f64 + 1.0)));
+            }
+        }
+    }
+    let total: f64 = brush.iter().map(|(_, _, weight)| weight).sum();
+    brush.iter().map(|(bx, by, weight)| (*bx, *by, weight / total)).collect()
+}
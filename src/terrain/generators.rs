@@ -0,0 +1,218 @@
+use log::{debug, error, info, trace, warn, LevelFilter};
+use noise::{NoiseFn, Perlin};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::constants::PI;
+use crate::terrain::TerrainGenerator;
+use crate::utils::grid::Grid;
+use crate::utils::io_util::load_heightmap;
+
+// Multi-octave Perlin noise with a softened valley floor and large scale ridges
+pub struct FbmRidgeGenerator {
+    pub env_seed: u32,
+    pub frequency: f64,
+    pub octaves: i32,
+    pub persistence: f64,
+    pub lacunarity: f64,
+    pub valley_floor: f64,
+    pub ridge_frequency: f64,
+    pub ridge_multiplier: f64,
+}
+
+impl TerrainGenerator for FbmRidgeGenerator {
+    fn name(&self) -> &str {
+        "fbm_ridge"
+    }
+
+    fn generate(&self, width: usize, height: usize, loop_step: i64) -> Grid<f64> {
+        let perlin = Perlin::new(self.env_seed);
+        let step_rate: f64 = 2.0;
+        let smoothing_factor: f64 = 0.1; // This adjusts how quickly the value approaches the floor
+        let min_value: f64 = self.valley_floor + (-1.0 - self.valley_floor) * smoothing_factor * 1.1;
+
+        trace!("FbmRidgeGenerator::generate >> Generating terrain");
+        Grid::par_from_fn(width, height, |x, y| {
+            let mut amplitude: f64 = 1.0;
+            let mut frequency = self.frequency;
+            let mut total: f64 = 0.0;
+            let mut fbm_max_value = 0.0;
+            // Multi-octave Perlin noise (Fractal Brownian Motion)
+            for _ in 0..self.octaves {
+                total += perlin.get([
+                    x as f64 * frequency,
+                    y as f64 * frequency,
+                    loop_step as f64 * step_rate * frequency,
+                ]) * amplitude;
+                fbm_max_value += amplitude;
+                amplitude *= self.persistence;
+                frequency *= self.lacunarity;
+            }
+            total /= fbm_max_value;
+
+            total += self.ridge_multiplier
+                * perlin.get([(x as f64) * self.ridge_frequency, (y as f64) * self.ridge_frequency]);
+
+            if total < self.valley_floor {
+                total = self.valley_floor + (total - self.valley_floor) * smoothing_factor;
+            }
+
+            (total - min_value) / (1.0 - min_value)
+        })
+    }
+}
+
+// Linear slope from low to high along the given angle, in radians
+pub struct GradientRampGenerator {
+    pub angle: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl TerrainGenerator for GradientRampGenerator {
+    fn name(&self) -> &str {
+        "ramp"
+    }
+
+    fn generate(&self, width: usize, height: usize, _loop_step: i64) -> Grid<f64> {
+        let (dir_x, dir_y) = (self.angle.cos(), self.angle.sin());
+        // Project the corners to find the extent of the ramp along its direction
+        let corners = [(0.0, 0.0), (width as f64, 0.0), (0.0, height as f64), (width as f64, height as f64)];
+        let projections: Vec<f64> = corners.iter().map(|(x, y)| x * dir_x + y * dir_y).collect();
+        let min_proj = projections.iter().cloned().fold(f64::MAX, f64::min);
+        let max_proj = projections.iter().cloned().fold(f64::MIN, f64::max);
+
+        Grid::par_from_fn(width, height, |x, y| {
+            let t = (x as f64 * dir_x + y as f64 * dir_y - min_proj) / (max_proj - min_proj);
+            self.low + (self.high - self.low) * t
+        })
+    }
+}
+
+// Smooth bumps rising out of a low sea floor, with a little noise on top
+pub struct IslandsGenerator {
+    pub env_seed: u32,
+    pub num_islands: usize,
+    pub island_radius: f64,
+}
+
+impl TerrainGenerator for IslandsGenerator {
+    fn name(&self) -> &str {
+        "islands"
+    }
+
+    fn generate(&self, width: usize, height: usize, _loop_step: i64) -> Grid<f64> {
+        let mut rng = StdRng::seed_from_u64(self.env_seed as u64);
+        let perlin = Perlin::new(self.env_seed);
+        let sea_floor: f64 = 0.1;
+        let islands: Vec<(f64, f64, f64)> = (0..self.num_islands)
+            .map(|_| {
+                let radius = self.island_radius * rng.gen_range(0.6..1.4);
+                (rng.gen_range(0.0..width as f64), rng.gen_range(0.0..height as f64), radius)
+            })
+            .collect();
+
+        Grid::par_from_fn(width, height, |x, y| {
+            let mut elevation: f64 = 0.0;
+            for (ix, iy, radius) in islands.iter() {
+                let dist = ((x as f64 - ix).powi(2) + (y as f64 - iy).powi(2)).sqrt() / radius;
+                if dist < 1.0 {
+                    elevation = elevation.max((1.0 - dist * dist).powi(2));
+                }
+            }
+            let detail = 0.1 * perlin.get([x as f64 * 0.02, y as f64 * 0.02]);
+            (sea_floor + (1.0 - sea_floor) * elevation + detail * elevation).clamp(0.0, 1.0)
+        })
+    }
+}
+
+// Flat plateaus of random height around random sites, separated by valleys along the borders
+pub struct VoronoiGenerator {
+    pub env_seed: u32,
+    pub num_sites: usize,
+}
+
+impl TerrainGenerator for VoronoiGenerator {
+    fn name(&self) -> &str {
+        "voronoi"
+    }
+
+    fn generate(&self, width: usize, height: usize, _loop_step: i64) -> Grid<f64> {
+        let mut rng = StdRng::seed_from_u64(self.env_seed as u64);
+        let border_width: f64 = 20.0;
+        let sites: Vec<(f64, f64, f64)> = (0..self.num_sites.max(2))
+            .map(|_| (rng.gen_range(0.0..width as f64), rng.gen_range(0.0..height as f64), rng.gen_range(0.2..1.0)))
+            .collect();
+
+        Grid::par_from_fn(width, height, |x, y| {
+            let mut nearest: f64 = f64::MAX;
+            let mut second: f64 = f64::MAX;
+            let mut site_height: f64 = 0.0;
+            for (sx, sy, sh) in sites.iter() {
+                let dist = ((x as f64 - sx).powi(2) + (y as f64 - sy).powi(2)).sqrt();
+                if dist < nearest {
+                    second = nearest;
+                    nearest = dist;
+                    site_height = *sh;
+                } else if dist < second {
+                    second = dist;
+                }
+            }
+            // Distance to the border between the two closest sites
+            let edge = ((second - nearest) / border_width).min(1.0);
+            site_height * edge * edge * (3.0 - 2.0 * edge)
+        })
+    }
+}
+
+pub struct FlatGenerator {
+    pub height: f64,
+}
+
+impl TerrainGenerator for FlatGenerator {
+    fn name(&self) -> &str {
+        "flat"
+    }
+
+    fn generate(&self, width: usize, height: usize, _loop_step: i64) -> Grid<f64> {
+        Grid::new(width, height, self.height)
+    }
+}
+
+// Concentric ridges around the centre of the world
+pub struct RingsGenerator {
+    pub ring_spacing: f64,
+}
+
+impl TerrainGenerator for RingsGenerator {
+    fn name(&self) -> &str {
+        "rings"
+    }
+
+    fn generate(&self, width: usize, height: usize, _loop_step: i64) -> Grid<f64> {
+        let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+        Grid::par_from_fn(width, height, |x, y| {
+            let dist = ((x as f64 - cx).powi(2) + (y as f64 - cy).powi(2)).sqrt();
+            0.5 + 0.5 * (2.0 * PI * dist / self.ring_spacing).cos()
+        })
+    }
+}
+
+pub struct HeightmapGenerator {
+    pub path: String,
+    // Used when the file can't be loaded, so a bad path still gives a world with some relief
+    pub fallback: FbmRidgeGenerator,
+}
+
+impl TerrainGenerator for HeightmapGenerator {
+    fn name(&self) -> &str {
+        "heightmap"
+    }
+
+    fn generate(&self, width: usize, height: usize, loop_step: i64) -> Grid<f64> {
+        load_heightmap(&self.path, width, height).unwrap_or_else(|e| {
+            error!("HeightmapGenerator::generate >> Failed to load heightmap {}, generating terrain instead: {}", self.path, e);
+            self.fallback.generate(width, height, loop_step)
+        })
+    }
+}
@@ -0,0 +1,83 @@
+pub mod erosion;
+pub mod generators;
+
+use crate::utils::grid::Grid;
+use log::{debug, error, info, trace, warn, LevelFilter};
+
+use crate::constants::{
+    TERRAIN_GENERATOR, TERRAIN_HEIGHTMAP_PATH, TERRAIN_FREQUENCY, TERRAIN_OCTAVES, TERRAIN_PERSISTENCE,
+    TERRAIN_LACUNARITY, TERRAIN_VALLEY_FLOOR, TERRAIN_RIDGE_FREQUENCY, TERRAIN_RIDGE_MULTIPLIER, TERRAIN_RAMP_ANGLE,
+    TERRAIN_NUM_ISLANDS, TERRAIN_ISLAND_RADIUS, TERRAIN_VORONOI_SITES, TERRAIN_FLAT_HEIGHT, TERRAIN_RING_SPACING,
+    TERRAIN_EROSION_ENABLED, TERRAIN_EROSION_DROPLETS, TERRAIN_THERMAL_ITERATIONS, TERRAIN_THERMAL_TALUS,
+};
+use erosion::{hydraulic_erosion, thermal_weathering, ErosionParams};
+use generators::{
+    FbmRidgeGenerator, GradientRampGenerator, IslandsGenerator, VoronoiGenerator, FlatGenerator, RingsGenerator,
+    HeightmapGenerator,
+};
+
+// Produces a height field with values in [0, 1]
+pub trait TerrainGenerator: Send + Sync {
+    fn name(&self) -> &str;
+    fn generate(&self, width: usize, height: usize, loop_step: i64) -> Grid<f64>;
+}
+
+pub fn terrain_generator_from_config(env_seed: u32) -> Box<dyn TerrainGenerator> {
+    // A heightmap path takes priority so hand made landscapes don't need the generator changed too
+    let name = if TERRAIN_HEIGHTMAP_PATH.is_empty() { TERRAIN_GENERATOR } else { "heightmap" };
+    debug!("terrain::terrain_generator_from_config >> {}", name);
+    match name {
+        "fbm_ridge" => Box::new(fbm_ridge_from_config(env_seed)),
+        "ramp" => Box::new(GradientRampGenerator { angle: TERRAIN_RAMP_ANGLE, low: 0.0, high: 1.0 }),
+        "islands" => Box::new(IslandsGenerator {
+            env_seed,
+            num_islands: TERRAIN_NUM_ISLANDS,
+            island_radius: TERRAIN_ISLAND_RADIUS,
+        }),
+        "voronoi" => Box::new(VoronoiGenerator { env_seed, num_sites: TERRAIN_VORONOI_SITES }),
+        "flat" => Box::new(FlatGenerator { height: TERRAIN_FLAT_HEIGHT }),
+        "rings" => Box::new(RingsGenerator { ring_spacing: TERRAIN_RING_SPACING }),
+        "heightmap" => Box::new(HeightmapGenerator {
+            path: TERRAIN_HEIGHTMAP_PATH.to_string(),
+            fallback: fbm_ridge_from_config(env_seed),
+        }),
+        _ => {
+            warn!("terrain::terrain_generator_from_config >> Unknown terrain generator {}, using flat", name);
+            Box::new(FlatGenerator { height: TERRAIN_FLAT_HEIGHT })
+        }
+    }
+}
+
+fn fbm_ridge_from_config(env_seed: u32) -> FbmRidgeGenerator {
+    FbmRidgeGenerator {
+        env_seed,
+        frequency: TERRAIN_FREQUENCY,
+        octaves: TERRAIN_OCTAVES,
+        persistence: TERRAIN_PERSISTENCE,
+        lacunarity: TERRAIN_LACUNARITY,
+        valley_floor: TERRAIN_VALLEY_FLOOR,
+        ridge_frequency: TERRAIN_RIDGE_FREQUENCY,
+        ridge_multiplier: TERRAIN_RIDGE_MULTIPLIER,
+    }
+}
+
+pub fn erode_terrain_from_config(terrain: &mut Grid<f64>, env_seed: u32) {
+    if !TERRAIN_EROSION_ENABLED {
+        return;
+    }
+    let params = ErosionParams {
+        num_droplets: TERRAIN_EROSION_DROPLETS,
+        max_lifetime: 60,
+        inertia: 0.05,
+        sediment_capacity_factor: 4.0,
+        min_sediment_capacity: 0.0001,
+        erode_speed: 0.3,
+        deposit_speed: 0.3,
+        evaporate_speed: 0.01,
+        gravity: 4.0,
+        erosion_radius: 3,
+    };
+    hydraulic_erosion(terrain, &params, env_seed);
+    thermal_weathering(terrain, TERRAIN_THERMAL_ITERATIONS, TERRAIN_THERMAL_TALUS, 0.5);
+    terrain.iter_mut().for_each(|val| *val = val.clamp(0.0, 1.0));
+}
@@ -0,0 +1,137 @@
+use std::io::BufRead;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+
+use log::{debug, error, info, trace, warn, LevelFilter};
+use rand::Rng;
+
+use crate::environment::Environment;
+use crate::params::PARAM_REGISTRY;
+use crate::utils::svg_util::export_svg_snapshot;
+use crate::utils::ui_util::hud::PopulationSample;
+
+pub const CONSOLE_HELP: [&str; 8] = [
+    "help                      this list",
+    "params                    every parameter with its value and range",
+    "get <param>               print one parameter",
+    "set <param> <value>       change a parameter while running",
+    "spawn [count] [x y]       add random cells, at a point if given",
+    "kill <id>                 remove a cell",
+    "snapshot                  save an SVG snapshot",
+    "stats                     population summary",
+];
+
+// Runs one console line against the environment. The text is what to show the user,
+// from either the window console or stdin.
+pub fn execute_command(line: &str, env: &mut Environment) -> Result<String, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    debug!("console_util::execute_command >> {:?}", words);
+    let parse_f64 = |word: &str| word.parse::<f64>().map_err(|_| format!("'{}' is not a number", word));
+
+    match words.as_slice() {
+        [] => Ok(String::new()),
+        ["help"] => Ok(CONSOLE_HELP.join("\n")),
+        ["params"] => Ok(PARAM_REGISTRY
+            .iter()
+            .map(|spec| format!("{:<26} {:>10.4}  [{}, {}]  {}", spec.name, (spec.get)(&env.params), spec.min, spec.max, spec.description))
+            .collect::<Vec<String>>()
+            .join("\n")),
+        ["get", name] => Ok(format!("{} = {}", name, env.params.get(name)?)),
+        ["set", name, value] => {
+            env.params.set(name, parse_f64(value)?)?;
+            Ok(format!("{} = {}", name, env.params.get(name)?))
+        }
+        ["spawn", rest @ ..] => {
+            let count = match rest.first() {
+                Some(word) => word.parse::<usize>().map_err(|_| format!("'{}' is not a count", word))?,
+                None => 1,
+            };
+            let step = env.step_stats.step;
+            let ids: Vec<i64> = match rest {
+                [] | [_] => {
+                    let mut rng = rand::thread_rng();
+                    (0..count)
+                        .filter_map(|_| {
+                            let x = rng.gen_range(0.0..env.width() as f64);
+                            let y = rng.gen_range(0.0..env.height() as f64);
+                            env.spawn_cell(x, y, None, step)
+                        })
+                        .collect()
+                }
+                [_, x, y] => env.spawn_cluster(parse_f64(x)?, parse_f64(y)?, 20.0, count, None, step),
+                _ => return Err("usage: spawn [count] [x y]".to_string()),
+            };
+            Ok(format!("spawned {} of {} cells", ids.len(), count))
+        }
+        ["kill", id] => {
+            let id = id.parse::<i64>().map_err(|_| format!("'{}' is not a cell id", id))?;
+            if env.kill_cell(id) {
+                Ok(format!("killed cell {}", id))
+            } else {
+                Err(format!("no cell with id {}", id))
+            }
+        }
+        ["snapshot"] => Ok(format!("saved {}", export_svg_snapshot(env)?)),
+        ["stats"] => {
+            let sample = PopulationSample::from_environment(env);
+            Ok(format!(
+                "step {}  population {}  +{} / -{}\nmean mass {:.1}  energy {:.1}  health {:.1}  age {:.1}\nmean repro cost {:.1}  light {:.3}",
+                sample.step,
+                sample.population,
+                sample.births,
+                sample.deaths,
+                sample.mean_mass,
+                sample.mean_energy,
+                sample.mean_health,
+                sample.mean_age,
+                sample.mean_reproduction_cost,
+                sample.mean_light_exposure
+            ))
+        }
+        [command, ..] => Err(format!("Unknown command '{}', try 'help'", command)),
+    }
+}
+
+// Reads commands from stdin on a background thread so the main loop never blocks on input
+pub struct StdinConsole {
+    receiver: Receiver<String>,
+}
+
+impl StdinConsole {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            for line in std::io::stdin().lock().lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        error!("StdinConsole >> Failed to read stdin: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        Self { receiver }
+    }
+
+    // Runs every line typed since the last call and prints the results
+    pub fn poll(&self, env: &mut Environment) {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(line) => match execute_command(&line, env) {
+                    Ok(output) => {
+                        if !output.is_empty() {
+                            println!("{}", output);
+                        }
+                    }
+                    Err(e) => println!("error: {}", e),
+                },
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use rayon::prelude::*;
+use std::ops::{Index, IndexMut};
+
+// Flat, row-major 2D storage. Rows are contiguous so a Grid lines up with image and
+// texture buffers, and indexing is a single multiply-add instead of two pointer hops.
+#[derive(Clone, Debug)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    data: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Self { width, height, data: vec![fill; width * height] }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(usize, usize) -> T) -> Self {
+        let mut data: Vec<T> = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                data.push(f(x, y));
+            }
+        }
+        Self { width, height, data }
+    }
+
+    pub fn par_from_fn(width: usize, height: usize, f: impl Fn(usize, usize) -> T + Sync + Send) -> Self
+    where
+        T: Send,
+    {
+        let data: Vec<T> = (0..width * height).into_par_iter().map(|i| f(i % width, i / width)).collect();
+        Self { width, height, data }
+    }
+
+    pub fn from_vec(width: usize, height: usize, data: Vec<T>) -> Result<Self, String> {
+        if data.len() != width * height {
+            return Err(format!("Grid::from_vec expected {} values, got {}", width * height, data.len()));
+        }
+        Ok(Self { width, height, data })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn in_bounds(&self, x: i64, y: i64) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    // Integer corners and fractional offsets for bilinear sampling, clamped to the edges.
+    // NaN positions end up at the origin instead of panicking.
+    pub fn bilinear_corners(&self, x: f64, y: f64) -> (usize, usize, usize, usize, f64, f64) {
+        let x = x.clamp(0.0, (self.width - 1) as f64);
+        let y = y.clamp(0.0, (self.height - 1) as f64);
+        let x0 = (x.floor() as usize).min(self.width - 1);
+        let y0 = (y.floor() as usize).min(self.height - 1);
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+        (x0, y0, x1, y1, x - x0 as f64, y - y0 as f64)
+    }
+
+    #[inline]
+    fn offset(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x < self.width && y < self.height {
+            Some(&self.data[self.offset(x, y)])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x < self.width && y < self.height {
+            let offset = self.offset(x, y);
+            Some(&mut self.data[offset])
+        } else {
+            None
+        }
+    }
+
+    // Out of range coordinates are pulled back to the nearest edge
+    pub fn get_clamped(&self, x: i64, y: i64) -> &T {
+        let x = x.clamp(0, self.width as i64 - 1) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        &self.data[self.offset(x, y)]
+    }
+
+    pub fn get_wrapped(&self, x: i64, y: i64) -> &T {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.rem_euclid(self.height as i64) as usize;
+        &self.data[self.offset(x, y)]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: T) {
+        if let Some(cell) = self.get_mut(x, y) {
+            *cell = value;
+        }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.data.iter_mut()
+    }
+
+    // Yields (x, y, value) in row-major order
+    pub fn enumerate(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        let width = self.width;
+        self.data.iter().enumerate().map(move |(i, value)| (i % width, i / width, value))
+    }
+
+    pub fn rows(&self) -> std::slice::Chunks<'_, T> {
+        self.data.chunks(self.width)
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid { width: self.width, height: self.height, data: self.data.iter().map(f).collect() }
+    }
+
+    pub fn par_map<U: Send>(&self, f: impl Fn(&T) -> U + Sync + Send) -> Grid<U>
+    where
+        T: Sync,
+    {
+        Grid { width: self.width, height: self.height, data: self.data.par_iter().map(f).collect() }
+    }
+
+    // Like par_map but the closure also gets the coordinates, for stencils over neighbours
+    pub fn par_map_xy<U: Send>(&self, f: impl Fn(usize, usize, &T) -> U + Sync + Send) -> Grid<U>
+    where
+        T: Sync,
+    {
+        let width = self.width;
+        let data: Vec<U> = self.data.par_iter().enumerate().map(|(i, value)| f(i % width, i / width, value)).collect();
+        Grid { width: self.width, height: self.height, data }
+    }
+
+    pub fn resample_nearest(&self, width: usize, height: usize) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let x_scale = self.width as f64 / width as f64;
+        let y_scale = self.height as f64 / height as f64;
+        Grid::from_fn(width, height, |x, y| {
+            let sx = ((x as f64 + 0.5) * x_scale) as usize;
+            let sy = ((y as f64 + 0.5) * y_scale) as usize;
+            self[(sx.min(self.width - 1), sy.min(self.height - 1))].clone()
+        })
+    }
+}
+
+impl Grid<f64> {
+    pub fn sample_bilinear(&self, x: f64, y: f64) -> f64 {
+        let (x0, y0, x1, y1, fx, fy) = self.bilinear_corners(x, y);
+        let top = self[(x0, y0)] * (1.0 - fx) + self[(x1, y0)] * fx;
+        let bottom = self[(x0, y1)] * (1.0 - fx) + self[(x1, y1)] * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    pub fn resample_bilinear(&self, width: usize, height: usize) -> Grid<f64> {
+        let x_scale = self.width as f64 / width as f64;
+        let y_scale = self.height as f64 / height as f64;
+        Grid::par_from_fn(width, height, |x, y| {
+            self.sample_bilinear((x as f64 + 0.5) * x_scale - 0.5, (y as f64 + 0.5) * y_scale - 0.5)
+        })
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(x < self.width && y < self.height, "Grid index ({}, {}) out of bounds", x, y);
+        &self.data[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    #[inline]
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(x < self.width && y < self.height, "Grid index ({}, {}) out of bounds", x, y);
+        let width = self.width;
+        &mut self.data[y * width + x]
+    }
+}
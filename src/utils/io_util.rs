@@ -0,0 +1,120 @@
+use image::imageops::FilterType;
+use image::codecs::png::{CompressionType, FilterType as PngFilterType, PngEncoder};
+use image::{ColorType, ImageBuffer, ImageEncoder, Luma, RgbaImage};
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::mpsc::{sync_channel, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use crate::utils::grid::Grid;
+use log::{debug, error, info, trace, warn, LevelFilter};
+
+// Loads a grayscale image as a height field in [0, 1], resampled to the world size
+pub fn load_heightmap(path: &str, width: usize, height: usize) -> Result<Grid<f64>, String> {
+    debug!("io_util::load_heightmap >> Loading {} as {}x{}", path, width, height);
+    let img = image::open(path).map_err(|e| e.to_string())?.to_luma16();
+    let img = if img.width() as usize != width || img.height() as usize != height {
+        image::imageops::resize(&img, width as u32, height as u32, FilterType::Triangle)
+    } else {
+        img
+    };
+
+    // Image buffers are row-major like Grid, so the samples convert in one pass
+    let values: Vec<f64> = img.into_raw().into_iter().map(|val| val as f64 / u16::MAX as f64).collect();
+    Grid::from_vec(width, height, values)
+}
+
+// Writes the height field as a 16 bit grayscale PNG so it can be loaded back without banding
+pub fn export_terrain_png(terrain: &Grid<f64>, path: &str) -> Result<(), String> {
+    let width = terrain.width() as u32;
+    let height = terrain.height() as u32;
+    debug!("io_util::export_terrain_png >> Writing {}x{} terrain to {}", width, height, path);
+    let values: Vec<u16> = terrain.iter().map(|val| (val.clamp(0.0, 1.0) * u16::MAX as f64).round() as u16).collect();
+    let img: ImageBuffer<Luma<u16>, Vec<u16>> = ImageBuffer::from_raw(width, height, values)
+        .ok_or_else(|| "Terrain size does not match image buffer".to_string())?;
+    img.save(path).map_err(|e| e.to_string())
+}
+
+// Maps a config name to a PNG compression level. Fast keeps up with capture every step,
+// best is worth it when frames are few and disk is tight.
+pub fn png_compression_from_name(name: &str) -> CompressionType {
+    match name {
+        "fast" => CompressionType::Fast,
+        "best" => CompressionType::Best,
+        "huffman" => CompressionType::Huffman,
+        "rle" => CompressionType::Rle,
+        "default" => CompressionType::Default,
+        _ => {
+            warn!("io_util::png_compression_from_name >> Unknown compression '{}', using default", name);
+            CompressionType::Default
+        }
+    }
+}
+
+pub fn save_png(img: &RgbaImage, path: &str, compression: CompressionType) -> Result<(), String> {
+    let file = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+    let encoder = PngEncoder::new_with_quality(file, compression, PngFilterType::Adaptive);
+    encoder.write_image(img.as_raw(), img.width(), img.height(), ColorType::Rgba8).map_err(|e| e.to_string())
+}
+
+struct PngJob {
+    img: RgbaImage,
+    path: String,
+}
+
+// Encodes captured frames on background threads so the main loop only pays for the
+// pixel copy. The queue is bounded: when the workers fall behind, submit blocks rather
+// than letting frames pile up in memory.
+pub struct PngWriterPool {
+    sender: Option<SyncSender<PngJob>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl PngWriterPool {
+    pub fn new(num_workers: usize, queue_len: usize, compression: CompressionType) -> Self {
+        let (sender, receiver) = sync_channel::<PngJob>(queue_len.max(1));
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..num_workers.max(1))
+            .map(|worker_id| {
+                let receiver = Arc::clone(&receiver);
+                thread::spawn(move || loop {
+                    // Hold the lock only while taking a job, not while encoding it
+                    let job = match receiver.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    trace!("PngWriterPool >> worker {} writing {}", worker_id, job.path);
+                    save_png(&job.img, &job.path, compression).unwrap_or_else(|e| {
+                        error!("Failed to write PNG {}: {}", job.path, e);
+                    });
+                })
+            })
+            .collect();
+        debug!("PngWriterPool::new >> {} workers, queue length {}", num_workers.max(1), queue_len.max(1));
+        Self { sender: Some(sender), workers }
+    }
+
+    pub fn submit(&self, img: RgbaImage, path: String) -> Result<(), String> {
+        let sender = self.sender.as_ref().ok_or_else(|| "PNG writer pool is shut down".to_string())?;
+        match sender.try_send(PngJob { img, path }) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(job)) => {
+                debug!("PngWriterPool::submit >> Queue full, waiting for a worker");
+                sender.send(job).map_err(|e| e.to_string())
+            }
+            Err(TrySendError::Disconnected(_)) => Err("PNG writer workers have stopped".to_string()),
+        }
+    }
+}
+
+impl Drop for PngWriterPool {
+    // Closing the channel lets the workers drain what's queued and exit
+    fn drop(&mut self) {
+        self.sender = None;
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                error!("PngWriterPool >> A PNG writer thread panicked");
+            }
+        }
+    }
+}
@@ -2,3 +2,9 @@ pub mod log_util;
 pub mod io_util;
 pub mod ui_util;
 pub mod math_util;
+pub mod grid;
+pub mod video_util;
+pub mod raster_util;
+pub mod svg_util;
+pub mod console_util;
+pub mod tui_util;
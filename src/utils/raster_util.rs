@@ -0,0 +1,148 @@
+use image::{Rgba, RgbaImage};
+use log::{debug, error, info, trace, warn, LevelFilter};
+use rayon::prelude::*;
+
+use crate::environment::Environment;
+use crate::utils::ui_util::camera::Camera;
+use crate::utils::ui_util::trails::Trails;
+use crate::utils::ui_util::{rbga_cell_lighting, terrain_color};
+
+// CPU rasterizer drawing the same picture as the SDL view, for runs without a window.
+// The lit terrain is cached at world resolution and only rebuilt when it changes.
+pub struct SoftwareRenderer {
+    pub width: u32,
+    pub height: u32,
+    terrain_pixels: Vec<[u8; 4]>,
+    terrain_version: Option<u64>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            terrain_pixels: Vec::new(),
+            terrain_version: None,
+        }
+    }
+
+    pub fn render(&mut self, env: &Environment, camera: &Camera, trails: Option<&Trails>) -> RgbaImage {
+        self.update_terrain(env);
+        let mut img = RgbaImage::new(self.width, self.height);
+        self.draw_terrain(env, camera, &mut img);
+        if let Some(trails) = trails.filter(|trails| trails.enabled) {
+            draw_trails(trails, camera, &mut img);
+        }
+        draw_cells(env, camera, &mut img);
+        img
+    }
+
+    fn update_terrain(&mut self, env: &Environment) {
+        if self.terrain_version == Some(env.terrain_version) {
+            return;
+        }
+        let width = env.width();
+        self.terrain_pixels = (0..env.width() * env.height())
+            .into_par_iter()
+            .map(|i| terrain_color(env, i % width, i / width))
+            .collect();
+        self.terrain_version = Some(env.terrain_version);
+        debug!("SoftwareRenderer::update_terrain >> Recoloured terrain version {}", env.terrain_version);
+    }
+
+    // Nearest-neighbour lookup through the camera, matching how SDL scales the terrain texture
+    fn draw_terrain(&self, env: &Environment, camera: &Camera, img: &mut RgbaImage) {
+        let (world_width, world_height) = (env.width() as f64, env.height() as f64);
+        let row_len = self.width as usize * 4;
+        img.par_chunks_mut(row_len).enumerate().for_each(|(screen_y, row)| {
+            for (screen_x, pixel) in row.chunks_exact_mut(4).enumerate() {
+                let (world_x, world_y) = camera.screen_to_world(screen_x as f64 + 0.5, screen_y as f64 + 0.5);
+                if world_x >= 0.0 && world_y >= 0.0 && world_x < world_width && world_y < world_height {
+                    let color = self.terrain_pixels[world_y as usize * env.width() + world_x as usize];
+                    pixel.copy_from_slice(&color);
+                } else {
+                    pixel.copy_from_slice(&[0, 0, 0, 255]);
+                }
+            }
+        });
+    }
+}
+
+// Same segments and colours as the window's trail layer, stepped one pixel at a time
+pub fn draw_trails(trails: &Trails, camera: &Camera, img: &mut RgbaImage) {
+    let (width, height) = (img.width() as f64, img.height() as f64);
+    for (p1, p2, color) in trails.segments() {
+        let (x1, y1) = camera.world_to_screen(p1.0, p1.1);
+        let (x2, y2) = camera.world_to_screen(p2.0, p2.1);
+        let num_steps = (x2 - x1).abs().max((y2 - y1).abs()).ceil().max(1.0) as usize;
+        for i in 0..=num_steps {
+            let t = i as f64 / num_steps as f64;
+            let (x, y) = (x1 + (x2 - x1) * t, y1 + (y2 - y1) * t);
+            if x >= 0.0 && y >= 0.0 && x < width && y < height {
+                blend_pixel(img, x as u32, y as u32, color);
+            }
+        }
+    }
+}
+
+// Membrane, inside and nucleus discs, the nucleus nudged toward the direction of travel
+pub fn draw_cells(env: &Environment, camera: &Camera, img: &mut RgbaImage) {
+    let membrane_width = (2.0 * camera.zoom).round().max(1.0);
+    for cell in env.cells.iter() {
+        if !cell.alive || !camera.is_visible(cell.x_pos, cell.y_pos, cell.radius) {
+            continue;
+        }
+        let (center_x, center_y) = camera.world_to_screen(cell.x_pos, cell.y_pos);
+        let radius = (cell.radius * camera.zoom).floor();
+
+        let membrane = rbga_cell_lighting(cell, env, "membrane");
+        let mut inside = rbga_cell_lighting(cell, env, "inside");
+        let nucleus = rbga_cell_lighting(cell, env, "nucleus");
+        if cell.id == 1 {
+            inside = [255, 255, 255, 255];
+        }
+
+        fill_circle(img, center_x, center_y, radius, membrane);
+        fill_circle(img, center_x, center_y, radius - membrane_width, inside);
+
+        let velocity_magnitude = (cell.x_vel.powi(2) + cell.y_vel.powi(2)).sqrt();
+        let (offset_x, offset_y) = if velocity_magnitude != 0.0 {
+            (
+                (cell.x_vel / velocity_magnitude * (radius / 4.0)).round(),
+                (cell.y_vel / velocity_magnitude * (radius / 4.0)).round(),
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        fill_circle(img, center_x + offset_x, center_y + offset_y, (radius * (2.0 / 5.0)).round(), nucleus);
+    }
+}
+
+pub fn fill_circle(img: &mut RgbaImage, center_x: f64, center_y: f64, radius: f64, color: [u8; 4]) {
+    if radius < 0.0 {
+        return;
+    }
+    let x_min = (center_x - radius).floor().max(0.0) as i64;
+    let y_min = (center_y - radius).floor().max(0.0) as i64;
+    let x_max = ((center_x + radius).ceil() as i64).min(img.width() as i64 - 1);
+    let y_max = ((center_y + radius).ceil() as i64).min(img.height() as i64 - 1);
+    let radius_sq = radius * radius;
+    for y in y_min..=y_max {
+        for x in x_min..=x_max {
+            let dx = x as f64 + 0.5 - center_x;
+            let dy = y as f64 + 0.5 - center_y;
+            if dx * dx + dy * dy <= radius_sq {
+                blend_pixel(img, x as u32, y as u32, color);
+            }
+        }
+    }
+}
+
+pub fn blend_pixel(img: &mut RgbaImage, x: u32, y: u32, color: [u8; 4]) {
+    let Rgba(dst) = img.get_pixel_mut(x, y);
+    let alpha = color[3] as u32;
+    for channel in 0..3 {
+        dst[channel] = ((color[channel] as u32 * alpha + dst[channel] as u32 * (255 - alpha)) / 255) as u8;
+    }
+    dst[3] = 255;
+}
@@ -0,0 +1,261 @@
+use std::fmt::Write as FmtWrite;
+
+use image::codecs::png::PngEncoder;
+use image::{ColorType, ImageEncoder};
+use log::{debug, error, info, trace, warn, LevelFilter};
+
+use crate::constants::{SVG_EXPORT_DIR, SVG_TERRAIN_MODE, SVG_CONTOUR_LEVELS, SVG_CONTOUR_STEP, SVG_ANNOTATE_CELLS, SVG_LINEAGE_COLORS};
+use crate::environment::Environment;
+use crate::utils::grid::Grid;
+use crate::utils::ui_util::overlay::id_color;
+use crate::utils::ui_util::{hsva_to_rgba, rbga_cell_lighting, terrain_color, OBSTACLE_COLOR};
+
+pub struct SvgOptions {
+    pub terrain_mode: String, // raster, contours or none
+    pub contour_levels: usize,
+    pub contour_step: usize, // Grid cells per marching squares sample
+    pub annotate_cells: bool,
+    pub lineage_colors: bool,
+}
+
+impl SvgOptions {
+    pub fn from_config() -> Self {
+        Self {
+            terrain_mode: SVG_TERRAIN_MODE.to_string(),
+            contour_levels: SVG_CONTOUR_LEVELS,
+            contour_step: SVG_CONTOUR_STEP,
+            annotate_cells: SVG_ANNOTATE_CELLS,
+            lineage_colors: SVG_LINEAGE_COLORS,
+        }
+    }
+}
+
+// Saves to SVG_EXPORT_DIR named after the current step, returning the path written
+pub fn export_svg_snapshot(env: &Environment) -> Result<String, String> {
+    std::fs::create_dir_all(SVG_EXPORT_DIR).map_err(|e| e.to_string())?;
+    let path = format!("{}/snapshot_{:08}.svg", SVG_EXPORT_DIR, env.step_stats.step);
+    export_svg(env, &path, &SvgOptions::from_config())?;
+    Ok(path)
+}
+
+// Writes the world as an SVG: terrain underneath, then every cell as vector circles
+// so the snapshot stays sharp at any print size
+pub fn export_svg(env: &Environment, path: &str, options: &SvgOptions) -> Result<(), String> {
+    debug!("svg_util::export_svg >> Writing {} cells to {}", env.cells.len(), path);
+    let svg = environment_to_svg(env, options)?;
+    std::fs::write(path, svg).map_err(|e| e.to_string())
+}
+
+pub fn environment_to_svg(env: &Environment, options: &SvgOptions) -> Result<String, String> {
+    let (width, height) = (env.width(), env.height());
+    let mut svg = String::new();
+    writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    )
+    .unwrap();
+    writeln!(svg, r#"<rect width="{}" height="{}" fill="black"/>"#, width, height).unwrap();
+
+    match options.terrain_mode.as_str() {
+        "raster" => write_terrain_raster(env, &mut svg)?,
+        "contours" => {
+            write_terrain_contours(&env.terrain, options.contour_levels, options.contour_step.max(1), &mut svg);
+            write_obstacles(env, &mut svg);
+        }
+        "none" => write_obstacles(env, &mut svg),
+        other => warn!("svg_util::environment_to_svg >> Unknown terrain mode '{}', leaving terrain out", other),
+    }
+
+    writeln!(svg, r#"<g id="cells">"#).unwrap();
+    for cell in env.cells.iter().filter(|cell| cell.alive) {
+        let membrane = rbga_cell_lighting(cell, env, "membrane");
+        let inside = if options.lineage_colors {
+            id_color(cell.lineage_id as u64)
+        } else if cell.id == 1 {
+            [255, 255, 255, 255]
+        } else {
+            rbga_cell_lighting(cell, env, "inside")
+        };
+        let nucleus = rbga_cell_lighting(cell, env, "nucleus");
+        let (x, y, radius) = (cell.x_pos, cell.y_pos, cell.radius);
+        let membrane_width = 2.0;
+
+        let speed = (cell.x_vel.powi(2) + cell.y_vel.powi(2)).sqrt();
+        let (dir_x, dir_y) = if speed != 0.0 { (cell.x_vel / speed, cell.y_vel / speed) } else { (0.0, 0.0) };
+
+        writeln!(svg, r#"<g id="cell-{}" data-lineage="{}" data-generation="{}">"#, cell.id, cell.lineage_id, cell.generation).unwrap();
+        writeln!(svg, r#"  <circle cx="{:.2}" cy="{:.2}" r="{:.2}" {}/>"#, x, y, radius, svg_paint("fill", membrane)).unwrap();
+        writeln!(svg, r#"  <circle cx="{:.2}" cy="{:.2}" r="{:.2}" {}/>"#, x, y, (radius - membrane_width).max(0.0), svg_paint("fill", inside)).unwrap();
+        writeln!(
+            svg,
+            r#"  <circle cx="{:.2}" cy="{:.2}" r="{:.2}" {}/>"#,
+            x + dir_x * radius / 4.0,
+            y + dir_y * radius / 4.0,
+            radius * 2.0 / 5.0,
+            svg_paint("fill", nucleus)
+        )
+        .unwrap();
+        if speed != 0.0 {
+            // Heading tick from the centre out past the membrane
+            writeln!(
+                svg,
+                r#"  <line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="white" stroke-opacity="0.6" stroke-width="0.75"/>"#,
+                x,
+                y,
+                x + dir_x * radius * 1.4,
+                y + dir_y * radius * 1.4
+            )
+            .unwrap();
+        }
+        if options.annotate_cells {
+            writeln!(
+                svg,
+                r#"  <text x="{:.2}" y="{:.2}" font-family="monospace" font-size="6" fill="white">{} L{}</text>"#,
+                x + radius + 1.0,
+                y - radius,
+                cell.id,
+                cell.lineage_id
+            )
+            .unwrap();
+        }
+        writeln!(svg, "</g>").unwrap();
+    }
+    writeln!(svg, "</g>").unwrap();
+    writeln!(svg, "</svg>").unwrap();
+    Ok(svg)
+}
+
+// The lit terrain as a PNG data URI, the same colours the window shows
+fn write_terrain_raster(env: &Environment, svg: &mut String) -> Result<(), String> {
+    let (width, height) = (env.width(), env.height());
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            pixels.extend_from_slice(&terrain_color(env, x, y));
+        }
+    }
+    let mut png = Vec::new();
+    PngEncoder::new(&mut png)
+        .write_image(&pixels, width as u32, height as u32, ColorType::Rgba8)
+        .map_err(|e| e.to_string())?;
+    writeln!(
+        svg,
+        r#"<image id="terrain" width="{}" height="{}" image-rendering="pixelated" href="data:image/png;base64,{}"/>"#,
+        width,
+        height,
+        base64_encode(&png)
+    )
+    .unwrap();
+    Ok(())
+}
+
+// One path per height level traced with marching squares
+fn write_terrain_contours(terrain: &Grid<f64>, num_levels: usize, step: usize, svg: &mut String) {
+    writeln!(svg, r#"<g id="terrain" fill="none" stroke-width="1">"#).unwrap();
+    for level_index in 1..=num_levels {
+        let level = level_index as f64 / (num_levels + 1) as f64;
+        let [r, g, b, _] = hsva_to_rgba(197.0 / 360.0, 0.5, (level * 0.9) as f32, 1.0);
+        let mut path = String::new();
+        for (x1, y1, x2, y2) in contour_segments(terrain, level, step) {
+            write!(path, "M{:.1} {:.1}L{:.1} {:.1}", x1, y1, x2, y2).unwrap();
+        }
+        if !path.is_empty() {
+            writeln!(svg, r#"<path stroke="rgb({},{},{})" d="{}"/>"#, r, g, b, path).unwrap();
+        }
+    }
+    writeln!(svg, "</g>").unwrap();
+}
+
+// The raster mode already paints obstacles into the terrain image. Without it they are
+// drawn from the solid grid, one rectangle per run of solid cells in each row.
+fn write_obstacles(env: &Environment, svg: &mut String) {
+    let solid = match env.obstacles.as_ref() {
+        Some(obstacles) => &obstacles.solid,
+        None => return,
+    };
+    let mut path = String::new();
+    for y in 0..solid.height() {
+        let mut x = 0;
+        while x < solid.width() {
+            if !solid[(x, y)] {
+                x += 1;
+                continue;
+            }
+            let run_start = x;
+            while x < solid.width() && solid[(x, y)] {
+                x += 1;
+            }
+            write!(path, "M{} {}h{}v1h-{}z", run_start, y, x - run_start, x - run_start).unwrap();
+        }
+    }
+    if !path.is_empty() {
+        writeln!(svg, r#"<path id="obstacles" {} d="{}"/>"#, svg_paint("fill", OBSTACLE_COLOR), path).unwrap();
+    }
+}
+
+pub fn contour_segments(terrain: &Grid<f64>, level: f64, step: usize) -> Vec<(f64, f64, f64, f64)> {
+    let mut segments = Vec::new();
+    let (width, height) = (terrain.width(), terrain.height());
+    if width <= step || height <= step {
+        return segments;
+    }
+    // Where the level crosses the edge between two corners
+    let lerp = |(xa, ya, va): (f64, f64, f64), (xb, yb, vb): (f64, f64, f64)| -> (f64, f64) {
+        let t = if (vb - va).abs() > f64::EPSILON { (level - va) / (vb - va) } else { 0.5 };
+        (xa + (xb - xa) * t, ya + (yb - ya) * t)
+    };
+    let mut y = 0;
+    while y + step < height {
+        let mut x = 0;
+        while x + step < width {
+            let (x0, y0, x1, y1) = (x as f64, y as f64, (x + step) as f64, (y + step) as f64);
+            let corners = [
+                (x0, y0, terrain[(x, y)]),
+                (x1, y0, terrain[(x + step, y)]),
+                (x1, y1, terrain[(x + step, y + step)]),
+                (x0, y1, terrain[(x, y + step)]),
+            ];
+            // Edge i joins corner i to corner i + 1
+            let crossings: Vec<(f64, f64)> = (0..4)
+                .filter_map(|i| {
+                    let (a, b) = (corners[i], corners[(i + 1) % 4]);
+                    if (a.2 >= level) != (b.2 >= level) { Some(lerp(a, b)) } else { None }
+                })
+                .collect();
+            // Two crossings is a single line; four is a saddle, paired in order
+            for pair in crossings.chunks_exact(2) {
+                segments.push((pair[0].0, pair[0].1, pair[1].0, pair[1].1));
+            }
+            x += step;
+        }
+        y += step;
+    }
+    segments
+}
+
+// A fill or stroke attribute, with alpha as a separate opacity attribute because SVG 1.1
+// has no rgba() paint and many print tools drop or blacken shapes that use it
+fn svg_paint(attribute: &str, [r, g, b, a]: [u8; 4]) -> String {
+    if a == 255 {
+        format!(r#"{}="rgb({},{},{})""#, attribute, r, g, b)
+    } else {
+        format!(r#"{0}="rgb({1},{2},{3})" {0}-opacity="{4:.3}""#, attribute, r, g, b, a as f64 / 255.0)
+    }
+}
+
+pub fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        encoded.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        encoded.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        encoded.push(if chunk.len() > 1 { ALPHABET[(n >> 6) as usize & 63] as char } else { '=' });
+        encoded.push(if chunk.len() > 2 { ALPHABET[n as usize & 63] as char } else { '=' });
+    }
+    encoded
+}
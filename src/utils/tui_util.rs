@@ -0,0 +1,324 @@
+use std::fmt::Write;
+use std::io::{self, Read, Write as _};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use image::{Rgba, RgbaImage};
+use log::{debug, error, info, trace, warn, LevelFilter};
+
+use crate::constants::{TUI_PANEL_WIDTH, TUI_TRUE_COLOR};
+use crate::environment::Environment;
+use crate::utils::raster_util::SoftwareRenderer;
+use crate::utils::ui_util::camera::Camera;
+use crate::utils::ui_util::controls::SimControl;
+use crate::utils::ui_util::hud::{Hud, PopulationSample};
+use crate::utils::ui_util::rbga_cell_lighting;
+
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const SIZE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const KEYS_HELP: &str = "space pause  n step  +/- speed  0 reset  tab fast-forward  s snapshot  q quit";
+
+fn stty(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("Failed to run stty: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("stty {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Raw keyboard input on the alternate screen. Dropping it puts the terminal back the way it was.
+pub struct Terminal {
+    saved_mode: String,
+    size: (u16, u16),
+    // None until a size query succeeds, so a failed one is retried on the next frame
+    size_checked: Option<Instant>,
+}
+
+impl Terminal {
+    pub fn enter() -> Result<Self, String> {
+        let saved_mode = stty(&["-g"])?;
+        // Ctrl-C arrives as a key too, so quitting always goes through Drop
+        stty(&["-icanon", "-echo", "-isig", "min", "1"])?;
+        let mut stdout = io::stdout();
+        stdout.write_all(b"\x1b[?1049h\x1b[?25l\x1b[2J").map_err(|e| e.to_string())?;
+        stdout.flush().map_err(|e| e.to_string())?;
+        debug!("Terminal::enter >> saved mode {}", saved_mode);
+        Ok(Self { saved_mode, size: (80, 24), size_checked: None })
+    }
+
+    // Columns and rows. Asking stty means starting a process, so the answer is kept and
+    // only refreshed about once a second to pick up resizes.
+    pub fn size(&mut self) -> (u16, u16) {
+        if self.size_checked.map_or(true, |checked| checked.elapsed() >= SIZE_CHECK_INTERVAL) {
+            match query_size() {
+                Some(size) => {
+                    self.size = size;
+                    self.size_checked = Some(Instant::now());
+                }
+                None => self.size_checked = None,
+            }
+        }
+        self.size
+    }
+
+    pub fn draw(&self, frame: &str) -> Result<(), String> {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(frame.as_bytes()).map_err(|e| e.to_string())?;
+        stdout.flush().map_err(|e| e.to_string())
+    }
+}
+
+fn query_size() -> Option<(u16, u16)> {
+    let size = stty(&["size"]).ok()?;
+    let mut parts = size.split_whitespace().map(|part| part.parse::<u16>().ok());
+    let rows = parts.next()??;
+    let cols = parts.next()??;
+    if cols > 0 && rows > 1 {
+        Some((cols, rows))
+    } else {
+        None
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(b"\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = stdout.flush();
+        if let Err(e) = stty(&[&self.saved_mode]) {
+            error!("Terminal::drop >> Failed to restore terminal: {}", e);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TuiKey {
+    Quit,
+    TogglePause,
+    Step,
+    SpeedUp,
+    SlowDown,
+    ResetSpeed,
+    FastForward,
+    Snapshot,
+}
+
+// Reads stdin a byte at a time on a background thread so the main loop never blocks
+pub struct KeyReader {
+    receiver: Receiver<u8>,
+    // The start of an escape sequence that may still be arriving
+    pending: Vec<u8>,
+}
+
+impl KeyReader {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        thread::spawn(move || {
+            let mut stdin = io::stdin();
+            let mut byte = [0u8; 1];
+            loop {
+                match stdin.read(&mut byte) {
+                    Ok(0) => break,
+                    Ok(_) => {
+                        if sender.send(byte[0]).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        error!("KeyReader >> Failed to read stdin: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        Self { receiver, pending: Vec::new() }
+    }
+
+    // Keys pressed since the last call. Escape sequences (arrow keys and the like) are
+    // skipped, and one cut off at the end is held back until the next call, so only a
+    // lone Escape quits.
+    pub fn poll(&mut self) -> Vec<TuiKey> {
+        let new_bytes: Vec<u8> = self.receiver.try_iter().collect();
+        if new_bytes.is_empty() && !self.pending.is_empty() {
+            // Nothing followed the held bytes, so an Escape on its own really was Escape
+            let held = std::mem::take(&mut self.pending);
+            return if held == [0x1b] { vec![TuiKey::Quit] } else { Vec::new() };
+        }
+        let mut bytes = std::mem::take(&mut self.pending);
+        bytes.extend(new_bytes);
+
+        let mut keys = Vec::new();
+        let mut i = 0;
+        while i < bytes.len() {
+            let key = match bytes[i] {
+                0x1b if i + 1 == bytes.len() => {
+                    self.pending = vec![0x1b];
+                    break;
+                }
+                0x1b if matches!(bytes[i + 1], b'[' | b'O') => {
+                    let start = i;
+                    i += 2;
+                    while i < bytes.len() && !(0x40..=0x7e).contains(&bytes[i]) {
+                        i += 1;
+                    }
+                    if i == bytes.len() {
+                        self.pending = bytes[start..].to_vec();
+                        break;
+                    }
+                    None
+                }
+                b'q' | b'Q' | 0x1b | 0x03 => Some(TuiKey::Quit),
+                b' ' => Some(TuiKey::TogglePause),
+                b'n' | b'.' => Some(TuiKey::Step),
+                b'+' | b'=' => Some(TuiKey::SpeedUp),
+                b'-' => Some(TuiKey::SlowDown),
+                b'0' | 0x7f | 0x08 => Some(TuiKey::ResetSpeed),
+                b'\t' => Some(TuiKey::FastForward),
+                b's' | b'S' => Some(TuiKey::Snapshot),
+                _ => None,
+            };
+            keys.extend(key);
+            i += 1;
+        }
+        keys
+    }
+}
+
+// The world rasterized at two pixels per character cell, one in the foreground and one
+// in the background of a half block, with the stats panel down the right-hand side
+pub struct TuiView {
+    renderer: SoftwareRenderer,
+    camera: Camera,
+    size: (u16, u16),
+}
+
+impl TuiView {
+    pub fn new(env: &Environment) -> Self {
+        Self {
+            renderer: SoftwareRenderer::new(1, 1),
+            camera: Camera::new(1, 1, env.width(), env.height()),
+            size: (0, 0),
+        }
+    }
+
+    pub fn render(&mut self, size: (u16, u16), env: &Environment, hud: &Hud, sim_control: &SimControl, message: &str) -> String {
+        let (cols, rows) = size;
+        let panel_width = if cols >= TUI_PANEL_WIDTH * 2 { TUI_PANEL_WIDTH } else { 0 };
+        let map_cols = (cols - panel_width).max(1) as u32;
+        let map_rows = (rows - 1).max(1) as u32;
+        if self.size != size {
+            debug!("TuiView::render >> terminal resized to {}x{}", cols, rows);
+            self.renderer = SoftwareRenderer::new(map_cols, map_rows * 2);
+            self.camera = Camera::new(map_cols, map_rows * 2, env.width(), env.height());
+            self.size = size;
+        }
+
+        let mut img = self.renderer.render(env, &self.camera, None);
+        // Most cells are smaller than a pixel at this scale, so always mark their centres
+        for cell in env.cells.iter().filter(|cell| cell.alive) {
+            let (x, y) = self.camera.world_to_screen(cell.x_pos, cell.y_pos);
+            if x >= 0.0 && y >= 0.0 && (x as u32) < img.width() && (y as u32) < img.height() {
+                img.put_pixel(x as u32, y as u32, Rgba(rbga_cell_lighting(cell, env, "inside")));
+            }
+        }
+
+        let panel = stats_panel(env, hud, sim_control, panel_width.saturating_sub(2) as usize);
+        let mut out = String::with_capacity((cols as usize * rows as usize) * 24);
+        out.push_str("\x1b[H");
+        for row in 0..map_rows {
+            let mut last_colors = None;
+            for col in 0..map_cols {
+                let colors = (img.get_pixel(col, row * 2).0, img.get_pixel(col, row * 2 + 1).0);
+                // Neighbouring cells are often the same colour, don't repeat the escape codes
+                if last_colors != Some(colors) {
+                    push_color(&mut out, 38, colors.0);
+                    push_color(&mut out, 48, colors.1);
+                    last_colors = Some(colors);
+                }
+                out.push('▀');
+            }
+            out.push_str("\x1b[0m");
+            if panel_width > 0 {
+                let line = panel.get(row as usize).map(String::as_str).unwrap_or("");
+                let _ = write!(out, "  {}", fit_to_width(line, panel_width as usize - 2));
+            }
+            out.push_str("\x1b[K\r\n");
+        }
+        let status = if message.is_empty() { KEYS_HELP.to_string() } else { format!("{}  |  {}", message, KEYS_HELP) };
+        let _ = write!(out, "\x1b[0;7m{}\x1b[0m\x1b[K", fit_to_width(&status, cols as usize));
+        out
+    }
+}
+
+fn push_color(out: &mut String, layer: u8, [r, g, b, _]: [u8; 4]) {
+    if TUI_TRUE_COLOR {
+        let _ = write!(out, "\x1b[{};2;{};{};{}m", layer, r, g, b);
+    } else {
+        // Nearest entry in the 6x6x6 colour cube of a 256 colour terminal
+        let level = |c: u8| ((c as u16 * 5 + 127) / 255) as u8;
+        let _ = write!(out, "\x1b[{};5;{}m", layer, 16 + 36 * level(r) + 6 * level(g) + level(b));
+    }
+}
+
+// Pads or cuts a line to exactly `width` characters
+fn fit_to_width(line: &str, width: usize) -> String {
+    let mut fitted: String = line.chars().take(width).collect();
+    let len = fitted.chars().count();
+    fitted.extend(std::iter::repeat(' ').take(width - len));
+    fitted
+}
+
+fn stats_panel(env: &Environment, hud: &Hud, sim_control: &SimControl, width: usize) -> Vec<String> {
+    let latest = hud.latest();
+    let mut lines = vec![
+        "EVOLUTION SIMULATOR".to_string(),
+        String::new(),
+        format!("step {}", latest.step),
+        format!("{:.1} steps/s", hud.steps_per_second()),
+        sim_control.label(),
+        String::new(),
+        format!("population {}", env.cells.len()),
+        format!("births {}  deaths {}", latest.births, latest.deaths),
+        String::new(),
+        format!("mean mass    {:>10.1}", latest.mean_mass),
+        format!("mean energy  {:>10.1}", latest.mean_energy),
+        format!("mean health  {:>10.1}", latest.mean_health),
+        format!("mean age     {:>10.1}", latest.mean_age),
+        format!("repro cost   {:>10.1}", latest.mean_reproduction_cost),
+        format!("light        {:>10.3}", latest.mean_light_exposure),
+        String::new(),
+    ];
+    let charts: [(&str, fn(&PopulationSample) -> f64); 3] = [
+        ("population", |sample| sample.population as f64),
+        ("mean energy", |sample| sample.mean_energy),
+        ("mean health", |sample| sample.mean_health),
+    ];
+    for (label, value_fn) in charts.iter() {
+        let values: Vec<f64> = hud.history.iter().map(value_fn).collect();
+        lines.push(label.to_string());
+        lines.push(sparkline(&values, width));
+    }
+    lines
+}
+
+// The history squeezed into `width` columns of block characters, scaled to its own range
+fn sparkline(values: &[f64], width: usize) -> String {
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+    let chunk_size = (values.len() + width - 1) / width;
+    let columns: Vec<f64> = values.chunks(chunk_size).map(|chunk| chunk.iter().sum::<f64>() / chunk.len() as f64).collect();
+    let min = columns.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = columns.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let range = (max - min).max(1e-9);
+    columns
+        .iter()
+        .map(|value| SPARK_CHARS[(((value - min) / range) * (SPARK_CHARS.len() - 1) as f64).round() as usize])
+        .collect()
+}
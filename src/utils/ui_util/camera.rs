@@ -0,0 +1,112 @@
+use log::{debug, error, info, trace, warn, LevelFilter};
+
+use crate::constants::{CAMERA_MIN_ZOOM, CAMERA_MAX_ZOOM, CAMERA_ZOOM_STEP};
+use crate::environment::Environment;
+
+// Maps world coordinates to screen pixels. The camera looks at (center_x, center_y)
+// and zoom is screen pixels per world unit.
+pub struct Camera {
+    pub center_x: f64,
+    pub center_y: f64,
+    pub zoom: f64,
+    pub viewport_width: u32,
+    pub viewport_height: u32,
+    pub follow_id: Option<i64>,
+}
+
+impl Camera {
+    pub fn new(viewport_width: u32, viewport_height: u32, world_width: usize, world_height: usize) -> Self {
+        let mut camera = Self {
+            center_x: 0.0,
+            center_y: 0.0,
+            zoom: 1.0,
+            viewport_width,
+            viewport_height,
+            follow_id: None,
+        };
+        camera.fit_world(world_width, world_height);
+        camera
+    }
+
+    // Zoom out so the whole world is visible and centred
+    pub fn fit_world(&mut self, world_width: usize, world_height: usize) {
+        self.center_x = world_width as f64 / 2.0;
+        self.center_y = world_height as f64 / 2.0;
+        self.zoom = f64::min(
+            self.viewport_width as f64 / world_width as f64,
+            self.viewport_height as f64 / world_height as f64,
+        );
+        self.follow_id = None;
+    }
+
+    pub fn world_to_screen(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x - self.center_x) * self.zoom + self.viewport_width as f64 / 2.0,
+            (y - self.center_y) * self.zoom + self.viewport_height as f64 / 2.0,
+        )
+    }
+
+    pub fn screen_to_world(&self, screen_x: f64, screen_y: f64) -> (f64, f64) {
+        (
+            (screen_x - self.viewport_width as f64 / 2.0) / self.zoom + self.center_x,
+            (screen_y - self.viewport_height as f64 / 2.0) / self.zoom + self.center_y,
+        )
+    }
+
+    // World rectangle currently on screen as (x_min, y_min, x_max, y_max)
+    pub fn visible_world_rect(&self) -> (f64, f64, f64, f64) {
+        let (x_min, y_min) = self.screen_to_world(0.0, 0.0);
+        let (x_max, y_max) = self.screen_to_world(self.viewport_width as f64, self.viewport_height as f64);
+        (x_min, y_min, x_max, y_max)
+    }
+
+    pub fn is_visible(&self, x: f64, y: f64, radius: f64) -> bool {
+        let (x_min, y_min, x_max, y_max) = self.visible_world_rect();
+        x + radius >= x_min && x - radius <= x_max && y + radius >= y_min && y - radius <= y_max
+    }
+
+    pub fn pan_by_screen(&mut self, dx: i32, dy: i32) {
+        self.center_x -= dx as f64 / self.zoom;
+        self.center_y -= dy as f64 / self.zoom;
+        // Dragging the view is an explicit request to stop tracking a cell
+        self.follow_id = None;
+    }
+
+    // Zoom in or out keeping the world point under the cursor fixed on screen
+    pub fn zoom_at(&mut self, screen_x: f64, screen_y: f64, wheel_steps: i32) {
+        let (world_x, world_y) = self.screen_to_world(screen_x, screen_y);
+        self.zoom = (self.zoom * CAMERA_ZOOM_STEP.powi(wheel_steps)).clamp(CAMERA_MIN_ZOOM, CAMERA_MAX_ZOOM);
+        if self.follow_id.is_none() {
+            let (new_x, new_y) = self.screen_to_world(screen_x, screen_y);
+            self.center_x += world_x - new_x;
+            self.center_y += world_y - new_y;
+        }
+        trace!("Camera::zoom_at >> zoom: {:.3}", self.zoom);
+    }
+
+    pub fn set_viewport(&mut self, viewport_width: u32, viewport_height: u32) {
+        self.viewport_width = viewport_width;
+        self.viewport_height = viewport_height;
+    }
+
+    pub fn toggle_follow(&mut self, cell_id: Option<i64>) {
+        self.follow_id = if self.follow_id.is_some() { None } else { cell_id };
+        debug!("Camera::toggle_follow >> follow_id: {:?}", self.follow_id);
+    }
+
+    // Keep the followed cell centred, dropping the follow once the cell has died
+    pub fn update_follow(&mut self, env: &Environment) {
+        if let Some(follow_id) = self.follow_id {
+            match env.cells.iter().find(|cell| cell.id == follow_id) {
+                Some(cell) => {
+                    self.center_x = cell.x_pos;
+                    self.center_y = cell.y_pos;
+                }
+                None => {
+                    debug!("Camera::update_follow >> Cell {} is gone, no longer following", follow_id);
+                    self.follow_id = None;
+                }
+            }
+        }
+    }
+}
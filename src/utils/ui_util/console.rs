@@ -0,0 +1,86 @@
+use std::collections::VecDeque;
+
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::video::Window;
+
+use crate::constants::CONSOLE_HISTORY_LEN;
+use crate::environment::Environment;
+use crate::utils::console_util::execute_command;
+
+const LINE_HEIGHT: i32 = 11;
+const PANEL_PADDING: i32 = 8;
+const VISIBLE_LINES: usize = 12;
+
+// The in-window command line, opened with the backquote key
+pub struct ConsoleState {
+    pub open: bool,
+    pub input: String,
+    pub output: VecDeque<String>,
+}
+
+impl ConsoleState {
+    pub fn new() -> Self {
+        Self { open: false, input: String::new(), output: VecDeque::with_capacity(CONSOLE_HISTORY_LEN) }
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn push_text(&mut self, text: &str) {
+        // The backquote that opened the console arrives as text too
+        self.input.extend(text.chars().filter(|c| *c != '`'));
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+    }
+
+    // Runs the typed line and keeps it, with its result, in the scrollback
+    pub fn submit(&mut self, env: &mut Environment) {
+        let line = std::mem::take(&mut self.input);
+        if line.trim().is_empty() {
+            return;
+        }
+        self.push_output(format!("> {}", line));
+        match execute_command(&line, env) {
+            Ok(text) => text.lines().for_each(|text_line| self.push_output(text_line.to_string())),
+            Err(e) => self.push_output(format!("error: {}", e)),
+        }
+    }
+
+    fn push_output(&mut self, line: String) {
+        if self.output.len() == CONSOLE_HISTORY_LEN {
+            self.output.pop_front();
+        }
+        self.output.push_back(line);
+    }
+}
+
+// Drawn along the bottom of the world view, newest output just above the input line
+pub fn render_console(console: &ConsoleState, view: Rect, canvas: &mut Canvas<Window>) -> Result<(), String> {
+    if !console.open {
+        return Ok(());
+    }
+    let lines: Vec<&String> = console.output.iter().rev().take(VISIBLE_LINES).collect();
+    let panel_height = ((lines.len() as i32 + 1) * LINE_HEIGHT + PANEL_PADDING * 2) as u32;
+    let panel = Rect::new(view.x(), view.bottom() - panel_height as i32, view.width(), panel_height);
+
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(10, 14, 20, 220));
+    canvas.fill_rect(panel)?;
+    canvas.set_blend_mode(BlendMode::None);
+
+    let text_x = (panel.x() + PANEL_PADDING) as i16;
+    let mut y = panel.bottom() - PANEL_PADDING - LINE_HEIGHT;
+    canvas.string(text_x, y as i16, &format!("> {}_", console.input), Color::RGB(255, 255, 255))?;
+    for line in lines {
+        y -= LINE_HEIGHT;
+        let color = if line.starts_with("error:") { Color::RGB(255, 130, 120) } else { Color::RGB(200, 210, 220) };
+        canvas.string(text_x, y as i16, line, color)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use std::time::Instant;
+
+use log::{debug, error, info, trace, warn, LevelFilter};
+
+use crate::constants::{TARGET_STEPS_PER_SECOND, MAX_STEPS_PER_FRAME, MIN_STEPS_PER_SECOND, MAX_STEPS_PER_SECOND, FAST_FORWARD_RENDER_EVERY, CAPTURE_EVERY_N_STEPS, TARGET_RENDER_FPS};
+
+// How many simulation steps the main loop may run before the next frame
+pub enum StepBudget {
+    Steps(u32),
+    // No rate target, keep stepping until the frame is due
+    Until(Instant),
+}
+
+impl StepBudget {
+    pub fn allows(&self, steps_run: u32) -> bool {
+        match self {
+            StepBudget::Steps(steps) => steps_run < *steps,
+            StepBudget::Until(deadline) => Instant::now() < *deadline,
+        }
+    }
+}
+
+// Playback state for the simulation: pause, single step and the target step rate.
+// The step rate is independent of how often the window is redrawn.
+pub struct SimControl {
+    pub paused: bool,
+    pub fast_forward: bool,
+    pub target_steps_per_second: f64, // 0 means unconstrained
+    step_requested: bool,
+    step_debt: f64,
+    last_tick: Instant,
+    steps_since_render: u32,
+}
+
+impl SimControl {
+    pub fn new() -> Self {
+        Self {
+            paused: false,
+            fast_forward: false,
+            target_steps_per_second: TARGET_STEPS_PER_SECOND.max(0.0),
+            step_requested: false,
+            step_debt: 0.0,
+            last_tick: Instant::now(),
+            steps_since_render: 0,
+        }
+    }
+
+    pub fn is_unconstrained(&self) -> bool {
+        self.fast_forward || self.target_steps_per_second <= 0.0
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        debug!("SimControl::toggle_pause >> paused: {}", self.paused);
+    }
+
+    // Advance exactly one step, pausing first if needed
+    pub fn request_step(&mut self) {
+        self.paused = true;
+        self.step_requested = true;
+    }
+
+    pub fn toggle_fast_forward(&mut self) {
+        self.fast_forward = !self.fast_forward;
+        debug!("SimControl::toggle_fast_forward >> fast_forward: {}", self.fast_forward);
+        if self.fast_forward && CAPTURE_EVERY_N_STEPS > 0 {
+            info!("SimControl::toggle_fast_forward >> Frame capture paused while fast-forwarding");
+        }
+    }
+
+    // Capturing renders after every captured step, which would undo fast-forward, so it
+    // pauses until fast-forward is turned off again
+    pub fn capture_allowed(&self) -> bool {
+        !self.fast_forward
+    }
+
+    pub fn speed_up(&mut self) {
+        if self.target_steps_per_second > 0.0 {
+            self.target_steps_per_second *= 2.0;
+            if self.target_steps_per_second > MAX_STEPS_PER_SECOND {
+                self.target_steps_per_second = 0.0;
+            }
+        }
+        debug!("SimControl::speed_up >> {}", self.label());
+        let capture_limit = (TARGET_RENDER_FPS as i64 * CAPTURE_EVERY_N_STEPS) as f64;
+        if CAPTURE_EVERY_N_STEPS > 0 && (self.target_steps_per_second <= 0.0 || self.target_steps_per_second > capture_limit) {
+            warn!(
+                "SimControl::speed_up >> Capturing every {} steps limits the simulation to about {} steps/s, fast-forward pauses capture",
+                CAPTURE_EVERY_N_STEPS, capture_limit
+            );
+        }
+    }
+
+    pub fn slow_down(&mut self) {
+        self.target_steps_per_second = if self.target_steps_per_second <= 0.0 {
+            MAX_STEPS_PER_SECOND
+        } else {
+            (self.target_steps_per_second / 2.0).max(MIN_STEPS_PER_SECOND)
+        };
+        debug!("SimControl::slow_down >> {}", self.label());
+    }
+
+    pub fn reset_speed(&mut self) {
+        self.target_steps_per_second = TARGET_STEPS_PER_SECOND.max(0.0);
+        self.fast_forward = false;
+    }
+
+    // Steps owed since the last call. A fixed rate accumulates fractional steps so slow
+    // rates still advance, and the catch-up is capped so a slow frame can't snowball.
+    pub fn step_budget(&mut self, frame_deadline: Instant) -> StepBudget {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_tick).as_secs_f64();
+        self.last_tick = now;
+
+        if self.paused {
+            self.step_debt = 0.0;
+            if self.step_requested {
+                self.step_requested = false;
+                return StepBudget::Steps(1);
+            }
+            return StepBudget::Steps(0);
+        }
+        if self.is_unconstrained() {
+            self.step_debt = 0.0;
+            return StepBudget::Until(frame_deadline);
+        }
+
+        self.step_debt += elapsed * self.target_steps_per_second;
+        let steps = self.step_debt.floor();
+        if steps > MAX_STEPS_PER_FRAME as f64 {
+            warn!("SimControl::step_budget >> Falling behind {:.0} steps, dropping the backlog", steps);
+            self.step_debt = 0.0;
+            return StepBudget::Steps(MAX_STEPS_PER_FRAME);
+        }
+        self.step_debt -= steps;
+        StepBudget::Steps(steps as u32)
+    }
+
+    // Hand back steps the frame didn't get to, e.g. when it stopped early to capture
+    pub fn finish_frame(&mut self, budget: &StepBudget, steps_run: u32) {
+        if let StepBudget::Steps(steps) = budget {
+            if !self.paused {
+                self.step_debt += steps.saturating_sub(steps_run) as f64;
+            }
+        }
+        self.steps_since_render += steps_run;
+    }
+
+    // Fast-forward skips drawing until enough steps have gone by
+    pub fn render_due(&self) -> bool {
+        !self.fast_forward || self.paused || self.steps_since_render >= FAST_FORWARD_RENDER_EVERY
+    }
+
+    pub fn mark_rendered(&mut self) {
+        self.steps_since_render = 0;
+    }
+
+    pub fn label(&self) -> String {
+        let state = if self.paused {
+            "paused"
+        } else if self.fast_forward && CAPTURE_EVERY_N_STEPS > 0 {
+            "fast-forward (capture paused)"
+        } else if self.fast_forward {
+            "fast-forward"
+        } else {
+            "running"
+        };
+        if self.target_steps_per_second > 0.0 {
+            format!("{}  target {} steps/s", state, self.target_steps_per_second)
+        } else {
+            format!("{}  unlimited steps/s", state)
+        }
+    }
+}
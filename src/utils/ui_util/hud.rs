@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::video::Window;
+
+use crate::constants::HUD_HISTORY_LEN;
+use crate::environment::Environment;
+
+const LINE_HEIGHT: i32 = 11;
+const PANEL_PADDING: i32 = 8;
+const CHART_WIDTH: u32 = 160;
+const CHART_HEIGHT: u32 = 22;
+
+// One sample per simulation step of the population-wide numbers
+#[derive(Clone, Copy, Default)]
+pub struct PopulationSample {
+    pub step: i64,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+    pub mean_mass: f64,
+    pub mean_energy: f64,
+    pub mean_health: f64,
+    pub mean_age: f64,
+    pub mean_reproduction_cost: f64,
+    pub mean_light_exposure: f64,
+}
+
+impl PopulationSample {
+    pub fn from_environment(env: &Environment) -> Self {
+        let population = env.cells.len();
+        let n = population.max(1) as f64;
+        let mean = |f: &dyn Fn(&crate::cell::Cell) -> f64| env.cells.iter().map(f).sum::<f64>() / n;
+        Self {
+            step: env.step_stats.step,
+            population,
+            births: env.step_stats.births,
+            deaths: env.step_stats.deaths,
+            mean_mass: mean(&|cell| cell.mass),
+            mean_energy: mean(&|cell| cell.energy),
+            mean_health: mean(&|cell| cell.health),
+            mean_age: mean(&|cell| cell.age as f64),
+            mean_reproduction_cost: mean(&|cell| cell.reproduction_cost),
+            mean_light_exposure: mean(&|cell| cell.light_exposure),
+        }
+    }
+}
+
+pub struct Hud {
+    pub visible: bool,
+    pub history: VecDeque<PopulationSample>,
+    steps_per_second: f64,
+    last_step_time: Option<Instant>,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self {
+            visible: true,
+            history: VecDeque::with_capacity(HUD_HISTORY_LEN),
+            steps_per_second: 0.0,
+            last_step_time: None,
+        }
+    }
+
+    pub fn record(&mut self, env: &Environment) {
+        let now = Instant::now();
+        if let Some(last_step_time) = self.last_step_time {
+            let step_secs = now.duration_since(last_step_time).as_secs_f64().max(1e-6);
+            // Exponential moving average so the readout doesn't flicker
+            self.steps_per_second = self.steps_per_second * 0.9 + (1.0 / step_secs) * 0.1;
+        }
+        self.last_step_time = Some(now);
+
+        if self.history.len() == HUD_HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(PopulationSample::from_environment(env));
+    }
+
+    pub fn latest(&self) -> PopulationSample {
+        self.history.back().copied().unwrap_or_default()
+    }
+
+    pub fn steps_per_second(&self) -> f64 {
+        self.steps_per_second
+    }
+}
+
+type ChartSpec = (&'static str, fn(&PopulationSample) -> f64, Color);
+
+const CHARTS: [ChartSpec; 6] = [
+    ("population", |sample| sample.population as f64, Color::RGB(240, 240, 240)),
+    ("mass", |sample| sample.mean_mass, Color::RGB(255, 180, 90)),
+    ("energy", |sample| sample.mean_energy, Color::RGB(255, 230, 90)),
+    ("health", |sample| sample.mean_health, Color::RGB(120, 230, 120)),
+    ("repro cost", |sample| sample.mean_reproduction_cost, Color::RGB(230, 120, 230)),
+    ("light", |sample| sample.mean_light_exposure, Color::RGB(120, 200, 255)),
+];
+
+pub fn render_hud(hud: &Hud, status: &str, canvas: &mut Canvas<Window>) -> Result<(), String> {
+    if !hud.visible {
+        return Ok(());
+    }
+    let latest = hud.latest();
+    let text_lines = [
+        format!("step {}   {:.1} steps/s", latest.step, hud.steps_per_second()),
+        format!("population {}   +{} / -{}", latest.population, latest.births, latest.deaths),
+    ];
+    let text_lines: Vec<String> = text_lines.into_iter().chain(status.lines().map(str::to_string)).collect();
+    let charts = CHARTS;
+
+    let row_height = CHART_HEIGHT as i32 + LINE_HEIGHT + 4;
+    // The built-in font is 8 pixels wide, widen the panel for long status lines
+    let longest_line = text_lines.iter().map(|line| line.len() as u32 * 8).max().unwrap_or(0);
+    let panel_width = (CHART_WIDTH + 120).max(longest_line) + PANEL_PADDING as u32 * 2;
+    let panel_height = (text_lines.len() as i32 * LINE_HEIGHT + charts.len() as i32 * row_height + PANEL_PADDING * 3) as u32;
+    let (panel_x, panel_y) = (PANEL_PADDING, PANEL_PADDING);
+
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(10, 14, 20, 190));
+    canvas.fill_rect(Rect::new(panel_x, panel_y, panel_width, panel_height))?;
+
+    let text_x = (panel_x + PANEL_PADDING) as i16;
+    let mut y = panel_y + PANEL_PADDING;
+    for line in text_lines.iter() {
+        canvas.string(text_x, y as i16, line, Color::RGB(230, 235, 240))?;
+        y += LINE_HEIGHT;
+    }
+    y += PANEL_PADDING;
+
+    for (label, value_fn, color) in charts.iter() {
+        let values: Vec<f64> = hud.history.iter().map(value_fn).collect();
+        let current = values.last().copied().unwrap_or(0.0);
+        canvas.string(text_x, y as i16, &format!("{} {:.2}", label, current), *color)?;
+        let chart = Rect::new(panel_x + PANEL_PADDING, y + LINE_HEIGHT, CHART_WIDTH, CHART_HEIGHT);
+        render_sparkline(&values, chart, *color, canvas)?;
+        y += row_height;
+    }
+    canvas.set_blend_mode(BlendMode::None);
+    Ok(())
+}
+
+// The same charts as the HUD, stretched to fill a side panel
+pub fn render_chart_panel(hud: &Hud, rect: Rect, canvas: &mut Canvas<Window>) -> Result<(), String> {
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(10, 14, 20, 255));
+    canvas.fill_rect(rect)?;
+    canvas.set_draw_color(Color::RGBA(200, 220, 255, 80));
+    canvas.draw_line((rect.x(), rect.y()), (rect.x(), rect.bottom()))?;
+
+    let row_height = (rect.height() as i32 - PANEL_PADDING) / CHARTS.len() as i32;
+    let chart_width = rect.width().saturating_sub(PANEL_PADDING as u32 * 2).max(1);
+    let chart_height = (row_height - LINE_HEIGHT - PANEL_PADDING).max(4) as u32;
+    let text_x = rect.x() + PANEL_PADDING;
+    let mut y = rect.y() + PANEL_PADDING;
+    for (label, value_fn, color) in CHARTS.iter() {
+        let values: Vec<f64> = hud.history.iter().map(value_fn).collect();
+        let current = values.last().copied().unwrap_or(0.0);
+        canvas.string(text_x as i16, y as i16, &format!("{} {:.2}", label, current), *color)?;
+        render_sparkline(&values, Rect::new(text_x, y + LINE_HEIGHT, chart_width, chart_height), *color, canvas)?;
+        y += row_height;
+    }
+    canvas.set_blend_mode(BlendMode::None);
+    Ok(())
+}
+
+// Line chart of the values scaled to fill the rectangle between their min and max
+pub fn render_sparkline(values: &[f64], rect: Rect, color: Color, canvas: &mut Canvas<Window>) -> Result<(), String> {
+    canvas.set_draw_color(Color::RGBA(255, 255, 255, 25));
+    canvas.fill_rect(rect)?;
+    if values.len() < 2 {
+        return Ok(());
+    }
+    let min_val = values.iter().cloned().fold(f64::MAX, f64::min);
+    let max_val = values.iter().cloned().fold(f64::MIN, f64::max);
+    let range = if max_val - min_val > f64::EPSILON { max_val - min_val } else { 1.0 };
+    let x_step = rect.width() as f64 / (HUD_HISTORY_LEN - 1) as f64;
+    // Right-align so the newest sample is always at the right edge
+    let x_start = rect.x() as f64 + rect.width() as f64 - x_step * (values.len() - 1) as f64;
+    let to_point = |i: usize, val: f64| -> (i16, i16) {
+        let x = x_start + i as f64 * x_step;
+        let y = rect.y() as f64 + rect.height() as f64 * (1.0 - (val - min_val) / range);
+        (x as i16, y as i16)
+    };
+    for i in 1..values.len() {
+        let (x1, y1) = to_point(i - 1, values[i - 1]);
+        let (x2, y2) = to_point(i, values[i]);
+        canvas.line(x1, y1, x2, y2, color)?;
+    }
+    Ok(())
+}
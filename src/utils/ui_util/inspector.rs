@@ -0,0 +1,85 @@
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::video::Window;
+
+use crate::cell::Cell;
+use crate::environment::Environment;
+use crate::utils::ui_util::camera::Camera;
+
+const LINE_HEIGHT: i32 = 11; // The SDL2_gfx font is 8x8
+const PANEL_PADDING: i32 = 8;
+const PANEL_WIDTH: u32 = 290;
+
+pub fn cell_property_lines(cell: &Cell) -> Vec<String> {
+    vec![
+        format!("Cell {}", cell.id),
+        format!("parent {}  lineage {}  gen {}", cell.parent_id, cell.lineage_id, cell.generation),
+        format!("age {}  born at step {}", cell.age, cell.creation_step),
+        String::new(),
+        format!("energy  {:>7.1} / {:.1}", cell.energy, cell.energy_capacity),
+        format!("health  {:>7.1} / {:.1}", cell.health, cell.health_capacity),
+        format!("mass    {:>7.1}  radius {:.1}", cell.mass, cell.radius),
+        format!("repro   {:>7.3}  {}", cell.reproduction_progress, if cell.reproducing { "reproducing" } else { "resting" }),
+        format!("light   {:>7.3}  efficiency", cell.light_consumtion_efficiency),
+        String::new(),
+        format!("pos     ({:.1}, {:.1})", cell.x_pos, cell.y_pos),
+        format!("vel     ({:.3}, {:.3})", cell.x_vel, cell.y_vel),
+        format!("heading {:.3}  speed {:.3}", cell.heading, cell.speed),
+        String::new(),
+        "senses".to_string(),
+        format!("  light        {:.3}", cell.light_exposure),
+        format!("  grad along   {:+.5}", cell.gravity_gradient_along_heading),
+        format!("  grad perp    {:+.5}", cell.gravity_gradient_perpendicular_heading),
+        String::new(),
+        "genome".to_string(),
+        format!("  repro cost   {:.2}", cell.reproduction_cost),
+        format!("  membrane     {:?}", &cell.membrane_color[..3]),
+        format!("  inside       {:?}", &cell.inside_color[..3]),
+        format!("  nucleus      {:?}", &cell.nucleus_color[..3]),
+    ]
+}
+
+pub fn render_selection(cell: &Cell, camera: &Camera, canvas: &mut Canvas<Window>) -> Result<(), String> {
+    let (screen_x, screen_y) = camera.world_to_screen(cell.x_pos, cell.y_pos);
+    let radius = (cell.radius * camera.zoom) as i16 + 4;
+    canvas.aa_circle(screen_x as i16, screen_y as i16, radius, Color::RGBA(255, 255, 255, 230))?;
+    canvas.aa_circle(screen_x as i16, screen_y as i16, radius + 1, Color::RGBA(0, 0, 0, 160))?;
+    Ok(())
+}
+
+// Live properties of the selected cell in a translucent box at the top right of the view
+pub fn render_inspector(cell: &Cell, view: Rect, canvas: &mut Canvas<Window>) -> Result<(), String> {
+    let lines = cell_property_lines(cell);
+    let panel_height = (lines.len() as i32 * LINE_HEIGHT + PANEL_PADDING * 2) as u32;
+    let panel_x = view.right() - PANEL_WIDTH as i32 - PANEL_PADDING;
+    let panel_y = view.y() + PANEL_PADDING;
+
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(Color::RGBA(10, 14, 20, 200));
+    canvas.fill_rect(Rect::new(panel_x, panel_y, PANEL_WIDTH, panel_height))?;
+    canvas.set_draw_color(Color::RGBA(200, 220, 255, 120));
+    canvas.draw_rect(Rect::new(panel_x, panel_y, PANEL_WIDTH, panel_height))?;
+
+    let text_x = panel_x + PANEL_PADDING;
+    for (i, line) in lines.iter().enumerate() {
+        let text_y = panel_y + PANEL_PADDING + i as i32 * LINE_HEIGHT;
+        canvas.string(text_x as i16, text_y as i16, line, Color::RGB(230, 235, 240))?;
+    }
+
+    // Colour swatches next to the genome colours
+    let swatch_x = panel_x + PANEL_WIDTH as i32 - PANEL_PADDING - 16;
+    let swatch_rows = [(lines.len() - 3, cell.membrane_color), (lines.len() - 2, cell.inside_color), (lines.len() - 1, cell.nucleus_color)];
+    for (row, [r, g, b, _]) in swatch_rows.iter() {
+        let swatch_y = panel_y + PANEL_PADDING + *row as i32 * LINE_HEIGHT;
+        canvas.set_draw_color(Color::RGB(*r, *g, *b));
+        canvas.fill_rect(Rect::new(swatch_x, swatch_y, 16, 8))?;
+    }
+    canvas.set_blend_mode(BlendMode::None);
+    Ok(())
+}
+
+pub fn selected_cell<'a>(env: &'a Environment, selected_id: Option<i64>) -> Option<&'a Cell> {
+    selected_id.and_then(|id| env.cells.iter().find(|cell| cell.id == id))
+}
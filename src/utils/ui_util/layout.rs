@@ -0,0 +1,62 @@
+use sdl2::rect::Rect;
+
+use crate::constants::{MINIMAP_SIZE, SIDE_PANEL_WIDTH};
+
+const MARGIN: i32 = 8;
+
+// Where each view goes in the window. Recomputed every frame from the output size, so
+// windowed, fullscreen and resized windows all lay out the same way.
+#[derive(Clone, Copy, Debug)]
+pub struct Layout {
+    pub window_width: u32,
+    pub window_height: u32,
+    // The camera's viewport, always anchored at the window's top left
+    pub world_view: Rect,
+    pub minimap: Option<Rect>,
+    pub side_panel: Option<Rect>,
+}
+
+impl Layout {
+    pub fn compute(
+        window_width: u32,
+        window_height: u32,
+        world_width: usize,
+        world_height: usize,
+        show_minimap: bool,
+        show_side_panel: bool,
+    ) -> Self {
+        // The side panel only takes space when it leaves a usable world view
+        let side_panel_width = if show_side_panel && window_width > SIDE_PANEL_WIDTH * 2 { SIDE_PANEL_WIDTH } else { 0 };
+        let world_view = Rect::new(0, 0, (window_width - side_panel_width).max(1), window_height.max(1));
+        let side_panel = if side_panel_width > 0 {
+            Some(Rect::new(world_view.width() as i32, 0, side_panel_width, window_height.max(1)))
+        } else {
+            None
+        };
+
+        // Bottom-right corner of the world view, fitted to the world's aspect ratio
+        let minimap = if show_minimap {
+            let max_size = MINIMAP_SIZE.min(world_view.width() / 3).min(world_view.height() / 3).max(16) as f64;
+            let scale = max_size / world_width.max(world_height) as f64;
+            let (width, height) = ((world_width as f64 * scale).round() as u32, (world_height as f64 * scale).round() as u32);
+            Some(Rect::new(
+                world_view.right() - width as i32 - MARGIN,
+                world_view.bottom() - height as i32 - MARGIN,
+                width.max(1),
+                height.max(1),
+            ))
+        } else {
+            None
+        };
+
+        Self { window_width, window_height, world_view, minimap, side_panel }
+    }
+
+    pub fn in_minimap(&self, x: i32, y: i32) -> bool {
+        self.minimap.map_or(false, |rect| rect.contains_point((x, y)))
+    }
+
+    pub fn in_world_view(&self, x: i32, y: i32) -> bool {
+        self.world_view.contains_point((x, y)) && !self.in_minimap(x, y)
+    }
+}
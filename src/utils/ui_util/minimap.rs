@@ -0,0 +1,64 @@
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::video::Window;
+
+use crate::constants::MINIMAP_DENSITY_BLOCK;
+use crate::environment::Environment;
+use crate::utils::ui_util::camera::Camera;
+use crate::utils::ui_util::overlay::{cell_density, heat_color};
+use crate::utils::ui_util::TerrainTexture;
+
+// World point under a pixel of the minimap
+pub fn minimap_to_world(rect: Rect, env: &Environment, x: i32, y: i32) -> (f64, f64) {
+    (
+        (x - rect.x()) as f64 / rect.width() as f64 * env.width() as f64,
+        (y - rect.y()) as f64 / rect.height() as f64 * env.height() as f64,
+    )
+}
+
+fn world_to_minimap(rect: Rect, env: &Environment, x: f64, y: f64) -> (i32, i32) {
+    (
+        rect.x() + (x / env.width() as f64 * rect.width() as f64).round() as i32,
+        rect.y() + (y / env.height() as f64 * rect.height() as f64).round() as i32,
+    )
+}
+
+// The whole world scaled into the corner: terrain, where the cells are crowded, and the
+// outline of what the main camera is looking at
+pub fn render_minimap(
+    env: &Environment,
+    camera: &Camera,
+    terrain_texture: &TerrainTexture,
+    rect: Rect,
+    canvas: &mut Canvas<Window>,
+) -> Result<(), String> {
+    canvas.copy(&terrain_texture.texture, None, rect)?;
+
+    canvas.set_blend_mode(BlendMode::Blend);
+    let block_size = MINIMAP_DENSITY_BLOCK.max(1);
+    let density = cell_density(env, block_size);
+    let max_density = density.iter().cloned().fold(1.0, f64::max);
+    for (bx, by, count) in density.enumerate() {
+        if *count == 0.0 {
+            continue;
+        }
+        let [r, g, b, _] = heat_color(count / max_density);
+        let (x1, y1) = world_to_minimap(rect, env, (bx * block_size) as f64, (by * block_size) as f64);
+        let (x2, y2) = world_to_minimap(rect, env, ((bx + 1) * block_size) as f64, ((by + 1) * block_size) as f64);
+        canvas.set_draw_color(Color::RGBA(r, g, b, 200));
+        canvas.fill_rect(Rect::new(x1, y1, (x2 - x1).max(1) as u32, (y2 - y1).max(1) as u32))?;
+    }
+
+    let (x_min, y_min, x_max, y_max) = camera.visible_world_rect();
+    let (x1, y1) = world_to_minimap(rect, env, x_min.max(0.0), y_min.max(0.0));
+    let (x2, y2) = world_to_minimap(rect, env, x_max.min(env.width() as f64), y_max.min(env.height() as f64));
+    if x2 > x1 && y2 > y1 {
+        canvas.set_draw_color(Color::RGBA(255, 255, 255, 230));
+        canvas.draw_rect(Rect::new(x1, y1, (x2 - x1) as u32, (y2 - y1) as u32))?;
+    }
+    canvas.set_draw_color(Color::RGBA(200, 220, 255, 160));
+    canvas.draw_rect(rect)?;
+    canvas.set_blend_mode(BlendMode::None);
+    Ok(())
+}
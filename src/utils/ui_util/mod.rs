@@ -0,0 +1,735 @@
+extern crate sdl2; // SDL2 library
+
+pub mod camera;
+pub mod console;
+pub mod controls;
+pub mod hud;
+pub mod inspector;
+pub mod layout;
+pub mod minimap;
+pub mod overlay;
+pub mod tools;
+pub mod trails;
+
+use crate::environment::Environment;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::EventPump;
+use sdl2::render::{Canvas, Texture, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+use sdl2::audio::{AudioCallback, AudioSpecDesired};
+use image::RgbaImage;
+use rayon::prelude::*;
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::{Point, Rect};
+use std::thread::sleep;
+use std::time::Duration;
+
+use env_logger::Builder;
+use log::{debug, error, info, trace, warn, LevelFilter};
+use crate::cell::Cell;
+use camera::Camera;
+use console::{render_console, ConsoleState};
+use controls::SimControl;
+use layout::Layout;
+use minimap::{minimap_to_world, render_minimap};
+use tools::{render_brush_cursor, Tool, ToolState};
+use trails::{render_trails, Trails};
+use overlay::{cell_overlay_color, render_contacts, render_terrain_overlay, render_velocity_vectors, CellColorScale, Overlays};
+use hud::{render_chart_panel, render_hud, Hud};
+use inspector::{render_inspector, render_selection, selected_cell};
+use crate::flow_field::FlowField;
+use crate::utils::svg_util::export_svg_snapshot;
+use crate::constants::{ENV_SEED, ENV_STEP, FULLSCREEN, HEIGHT, LOG_LEVEL, WIDTH, PI, FLOW_RENDER_STREAMLINES, FLOW_STREAMLINE_SPACING, RECORD_VIDEO, MINIMAP_VISIBLE, SIDE_PANEL_VISIBLE, BRUSH_RADIUS, BRUSH_CELLS_PER_DAB, TERRAIN_BRUSH_STRENGTH};
+
+pub const OBSTACLE_COLOR: [u8; 4] = [70, 62, 56, 255];
+
+// Interactive state that lives across frames, driven by handle_events
+pub struct UIState {
+    pub should_render: bool,
+    pub should_exit: bool,
+    pub camera: Camera,
+    pub mouse_x: i32,
+    pub mouse_y: i32,
+    pub selected_id: Option<i64>,
+    pub hud: Hud,
+    pub sim_control: SimControl,
+    pub recording: bool,
+    pub overlays: Overlays,
+    pub trails: Trails,
+    pub tools: ToolState,
+    pub layout: Layout,
+    // Drawable pixels per window coordinate, above 1 on HiDPI displays
+    pub pixel_scale: (f64, f64),
+    pub show_minimap: bool,
+    pub show_side_panel: bool,
+    pub console: ConsoleState,
+    click_origin: Option<(i32, i32)>,
+    minimap_drag: bool,
+}
+
+impl UIState {
+    pub fn new(viewport_width: u32, viewport_height: u32, env: &Environment) -> Self {
+        Self {
+            should_render: true,
+            should_exit: false,
+            camera: Camera::new(viewport_width, viewport_height, env.width(), env.height()),
+            mouse_x: 0,
+            mouse_y: 0,
+            selected_id: None,
+            hud: Hud::new(),
+            sim_control: SimControl::new(),
+            recording: RECORD_VIDEO,
+            overlays: Overlays::new(),
+            trails: Trails::new(),
+            tools: ToolState::new(),
+            layout: Layout::compute(viewport_width, viewport_height, env.width(), env.height(), MINIMAP_VISIBLE, SIDE_PANEL_VISIBLE),
+            pixel_scale: (1.0, 1.0),
+            show_minimap: MINIMAP_VISIBLE,
+            show_side_panel: SIDE_PANEL_VISIBLE,
+            console: ConsoleState::new(),
+            click_origin: None,
+            minimap_drag: false,
+        }
+    }
+
+    // Lays the views out for the current window size and points the camera at the world view
+    pub fn update_layout(&mut self, window_width: u32, window_height: u32, env: &Environment) {
+        self.layout = Layout::compute(window_width, window_height, env.width(), env.height(), self.show_minimap, self.show_side_panel);
+        self.camera.set_viewport(self.layout.world_view.width(), self.layout.world_view.height());
+    }
+
+    // Mouse events arrive in window coordinates, everything is drawn in pixels
+    pub fn to_pixels(&self, x: i32, y: i32) -> (i32, i32) {
+        ((x as f64 * self.pixel_scale.0).round() as i32, (y as f64 * self.pixel_scale.1).round() as i32)
+    }
+
+    pub fn status_line(&self) -> String {
+        let status = format!("{}\n{}\n{}", self.sim_control.label(), self.overlays.label(), self.tools.label());
+        let status = if self.should_render { status } else { format!("{}  rendering off", status) };
+        if self.recording {
+            format!("{}  REC", status)
+        } else {
+            status
+        }
+    }
+}
+
+pub struct UIContext {
+    pub sdl_context: sdl2::Sdl,
+    pub event_pump: sdl2::EventPump,
+    pub canvas: sdl2::render::Canvas<sdl2::video::Window>,
+    pub audio_subsystem: sdl2::AudioSubsystem,
+}
+
+pub fn init_sdl() -> Result<(UIContext, u32, u32), String> {
+    debug!("ui_utils::init_sdl Initializing SDL...");
+    let sdl_context = sdl2::init()?;
+    debug!("ui_utils::init_sdl Initializing video subsystem...");
+    let video_subsystem = sdl_context.video()?;
+    debug!("ui_utils::init_sdl Initializing audio subsystem...");
+    let audio_subsystem = sdl_context.audio().unwrap();
+    debug!("ui_utils::init_sdl Initializing audio spec...");
+    let desired_spec = AudioSpecDesired {
+        freq: Some(44100),  // or 48000, or another standard rate
+        channels: Some(1),  // mono output
+        samples: None       // default sample size
+    };
+    debug!("ui_utils::init_sdl Initializing Window...");
+    let mut window = video_subsystem
+        .window("🧬 Evolution Simulator", WIDTH, HEIGHT)
+        .position_centered()
+        .resizable()
+        .allow_highdpi()
+        .build()
+        .map_err(|e| e.to_string())?;
+    debug!("ui_utils::init_sdl Initializing set height and width...");
+
+    if FULLSCREEN {
+        window
+            .set_fullscreen(sdl2::video::FullscreenType::Desktop)
+            .unwrap();
+    }
+
+    debug!("ui_utils::init_sdl canvas...");
+
+    let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+    // Drawable size in pixels, larger than the window size on HiDPI displays
+    let (actual_width, actual_height) = canvas.output_size()?;
+    debug!("ui_utils::init_sdl >> window: {:?} drawable: {}x{}", canvas.window().size(), actual_width, actual_height);
+
+    debug!("ui_utils::init_sdl event pump...");
+    let event_pump = sdl_context.event_pump()?;
+
+    Ok((
+        UIContext {
+            sdl_context,
+            event_pump,
+            canvas,
+            audio_subsystem,
+        },
+        actual_width,
+        actual_height,
+    ))
+}
+
+pub fn render_current_state(
+    env: &mut Environment,
+    ui_state: &mut UIState,
+    terrain_texture: &mut TerrainTexture,
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+) -> Result<(), String> {
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    canvas.clear();
+
+    let (output_width, output_height) = canvas.output_size()?;
+    let (window_width, window_height) = canvas.window().size();
+    ui_state.pixel_scale = (
+        output_width as f64 / window_width.max(1) as f64,
+        output_height as f64 / window_height.max(1) as f64,
+    );
+    ui_state.update_layout(output_width, output_height, env);
+    let layout = ui_state.layout;
+    ui_state.camera.update_follow(env);
+    let camera = &ui_state.camera;
+    // Keep the world from drawing under the side panel
+    canvas.set_clip_rect(layout.world_view);
+    render_terrain(env, camera, terrain_texture, canvas)?;
+    if FLOW_RENDER_STREAMLINES {
+        if let Some(flow_field) = env.flow_field.as_ref() {
+            render_flow_field(flow_field, camera, canvas)?;
+        }
+    }
+    render_terrain_overlay(env, ui_state.overlays.terrain, camera, canvas)?;
+    render_trails(&ui_state.trails, camera, canvas)?;
+    render_cells(env, &ui_state.overlays, camera, canvas)?;
+    if ui_state.overlays.contacts {
+        render_contacts(env, camera, canvas)?;
+    }
+    if ui_state.overlays.velocity_vectors {
+        render_velocity_vectors(env, camera, canvas)?;
+    }
+
+    match selected_cell(env, ui_state.selected_id) {
+        Some(cell) => {
+            render_selection(cell, camera, canvas)?;
+            render_inspector(cell, layout.world_view, canvas)?;
+        }
+        None => ui_state.selected_id = None,
+    }
+    if matches!(ui_state.tools.tool, Tool::Brush | Tool::Terrain) && layout.in_world_view(ui_state.mouse_x, ui_state.mouse_y) {
+        render_brush_cursor(ui_state.mouse_x, ui_state.mouse_y, canvas)?;
+    }
+    if let Some(rect) = layout.minimap {
+        render_minimap(env, camera, terrain_texture, rect, canvas)?;
+    }
+    render_hud(&ui_state.hud, &ui_state.status_line(), canvas)?;
+    render_console(&ui_state.console, layout.world_view, canvas)?;
+    canvas.set_clip_rect(None);
+    if let Some(rect) = layout.side_panel {
+        render_chart_panel(&ui_state.hud, rect, canvas)?;
+    }
+
+    canvas.present();
+    Ok(())
+}
+
+// Used while world rendering is off, so the run can still be judged from the charts
+pub fn render_hud_only(
+    ui_state: &UIState,
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+) -> Result<(), String> {
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    canvas.clear();
+    render_hud(&ui_state.hud, &ui_state.status_line(), canvas)?;
+    render_console(&ui_state.console, ui_state.layout.world_view, canvas)?;
+    canvas.present();
+    Ok(())
+}
+
+pub fn terrain_color(env: &Environment, x: usize, y: usize) -> [u8; 4] {
+    let min_bright_val = 0.0;
+    let max_bright_val = 0.9;
+
+    let val = env.terrain[(x, y)];
+    let mut rescaled_val = min_bright_val + ((val - 0.0) / (1.0 - 0.0)) * (max_bright_val - min_bright_val);
+    if let Some(obstacles) = env.obstacles.as_ref() {
+        if obstacles.solid[(x, y)] {
+            return OBSTACLE_COLOR;
+        }
+        rescaled_val *= obstacles.light_mask[(x, y)];
+    }
+    hsva_to_rgba(197.0/360.0, 0.5, rescaled_val as f32, 1.0)
+}
+
+// Fills an RGBA32 buffer, row by row, with the lit terrain colours
+pub fn colorize_terrain(env: &Environment, pixels: &mut [u8]) {
+    let width = env.width();
+    pixels.par_chunks_mut(4).enumerate().for_each(|(i, pixel)| {
+        pixel.copy_from_slice(&terrain_color(env, i % width, i / width));
+    });
+}
+
+// The terrain only changes when the environment bumps terrain_version, so it is
+// colourized once into a streaming texture and just copied to the canvas each frame
+pub struct TerrainTexture<'a> {
+    texture: Texture<'a>,
+    pixels: Vec<u8>,
+    terrain_version: Option<u64>,
+}
+
+impl<'a> TerrainTexture<'a> {
+    pub fn new(texture_creator: &'a TextureCreator<WindowContext>, width: u32, height: u32) -> Result<Self, String> {
+        let texture = texture_creator
+            .create_texture_streaming(PixelFormatEnum::RGBA32, width, height)
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            texture,
+            pixels: vec![0; (width * height * 4) as usize],
+            terrain_version: None,
+        })
+    }
+
+    pub fn update(&mut self, env: &Environment) -> Result<(), String> {
+        if self.terrain_version == Some(env.terrain_version) {
+            return Ok(());
+        }
+        debug!("TerrainTexture::update >> Uploading terrain version {}", env.terrain_version);
+        colorize_terrain(env, &mut self.pixels);
+        self.texture
+            .update(None, &self.pixels, env.width() * 4)
+            .map_err(|e| e.to_string())?;
+        self.terrain_version = Some(env.terrain_version);
+        Ok(())
+    }
+}
+
+pub fn render_terrain(
+    env: &Environment,
+    camera: &Camera,
+    terrain_texture: &mut TerrainTexture,
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+) -> Result<(), String> {
+    terrain_texture.update(env)?;
+    let (x_min, y_min) = camera.world_to_screen(0.0, 0.0);
+    let (x_max, y_max) = camera.world_to_screen(env.width() as f64, env.height() as f64);
+    let dst = Rect::new(
+        x_min.round() as i32,
+        y_min.round() as i32,
+        (x_max - x_min).round().max(1.0) as u32,
+        (y_max - y_min).round().max(1.0) as u32,
+    );
+    canvas.copy(&terrain_texture.texture, None, dst)?;
+    Ok(())
+}
+
+pub fn render_flow_field(
+    flow_field: &FlowField,
+    camera: &Camera,
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+) -> Result<(), String> {
+    // Seed streamlines on a world grid that keeps the on-screen spacing constant
+    let spacing = FLOW_STREAMLINE_SPACING as f64 / camera.zoom;
+    let num_steps = 12;
+    let step_len = spacing / num_steps as f64 * 1.5;
+    let (x_min, y_min, x_max, y_max) = camera.visible_world_rect();
+
+    let mut x = (x_min / spacing).floor() * spacing + spacing / 2.0;
+    while x < x_max {
+        let mut y = (y_min / spacing).floor() * spacing + spacing / 2.0;
+        while y < y_max {
+            let points = flow_field.streamline(x, y, num_steps, step_len);
+            // Fade each streamline out along its length so the direction is readable
+            for (i, pair) in points.windows(2).enumerate() {
+                let alpha = (160.0 * (1.0 - i as f64 / num_steps as f64)) as u8;
+                let (x1, y1) = camera.world_to_screen(pair[0].0, pair[0].1);
+                let (x2, y2) = camera.world_to_screen(pair[1].0, pair[1].1);
+                canvas.aa_line(x1 as i16, y1 as i16, x2 as i16, y2 as i16, Color::RGBA(220, 240, 255, alpha))?;
+            }
+            y += spacing;
+        }
+        x += spacing;
+    }
+    Ok(())
+}
+
+// Copies the presented frame out of the renderer in one go
+pub fn read_canvas_rgba(canvas: &Canvas<Window>) -> Result<RgbaImage, String> {
+    let (width, height) = canvas.output_size()?;
+    // ABGR8888 is packed little-endian, so the bytes come out in RGBA order
+    let pixels = canvas.read_pixels(None, PixelFormatEnum::ABGR8888)?;
+    RgbaImage::from_raw(width, height, pixels).ok_or_else(|| "Canvas size does not match pixel buffer".to_string())
+}
+
+pub fn render_cells(
+    env: &Environment,
+    overlays: &Overlays,
+    camera: &Camera,
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+) -> Result<(), String> {
+    let membrane_width = (2.0 * camera.zoom).round().max(1.0) as i16;
+    let color_scale = CellColorScale::from_environment(env);
+    for cell in env.cells.iter() {
+        if cell.alive && camera.is_visible(cell.x_pos, cell.y_pos, cell.radius) {
+            let (screen_x, screen_y) = camera.world_to_screen(cell.x_pos, cell.y_pos);
+            let center_x = screen_x as i16;
+            let center_y = screen_y as i16;
+            let radius = (cell.radius * camera.zoom) as i16;
+
+            let [mut r_mem, mut g_mem, mut b_mem, mut a_mem] = rbga_cell_lighting(cell, env, "membrane");
+
+            let [mut r_in, mut b_in, mut g_in, mut a_in] = rbga_cell_lighting(cell, env, "inside");
+
+            let [mut r_nuc, mut b_nuc, mut g_nuc, mut a_nuc] = rbga_cell_lighting(cell, env, "nucleus");
+            if cell.id == 1 {
+                r_in = 255;
+                b_in = 255;
+                g_in = 255;
+                a_in = 255;
+            }
+            // Data views paint the body in the overlay colour and keep the membrane dark for contrast
+            if let Some([r, g, b, a]) = cell_overlay_color(cell, overlays.cell_color, &color_scale) {
+                (r_mem, g_mem, b_mem, a_mem) = (r / 3, g / 3, b / 3, a);
+                // The body and nucleus locals are named in rbga order but hold r, g, b, a
+                (r_in, b_in, g_in, a_in) = (r, g, b, a);
+                (r_nuc, b_nuc, g_nuc, a_nuc) = (r / 2, g / 2, b / 2, a);
+            }
+
+            // Draw outer circle
+            canvas.filled_circle(center_x, center_y, radius, (r_mem, g_mem, b_mem, a_mem))?;
+
+            // Draw mid-circle
+            canvas.filled_circle(center_x, center_y, radius - membrane_width, Color::RGBA(r_in, b_in, g_in, a_in),)?;
+
+            // Calculate the offset based on the velocity vector
+            let velocity_magnitude = (cell.x_vel.powi(2) + cell.y_vel.powi(2)).sqrt();
+            if velocity_magnitude != 0.0 {
+                let offset_x = (cell.x_vel / velocity_magnitude * (radius as f64 / 4.0)).round() as i16;
+                let offset_y = (cell.y_vel / velocity_magnitude * (radius as f64 / 4.0)).round() as i16;
+
+                canvas.filled_circle(center_x + offset_x, center_y + offset_y, (radius as f64 * (2.0/5.0)).round() as i16, (r_nuc, b_nuc, g_nuc, a_nuc))?;
+            } else {
+                canvas.filled_circle(center_x, center_y, (radius as f64 * (2.0/5.0)).round() as i16, (r_nuc, b_nuc, g_nuc, a_nuc))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+
+
+pub fn rbga_cell_lighting(cell: &Cell, env: &Environment, color_type: &str) -> [u8; 4] {
+    let lowest_cell_brightness = 0.2;
+    let (mut h, mut s, mut v, mut a) = (0.0, 0.0, 0.0, 0.0);
+    if color_type == "inside"{
+        (h, s, v, a) = rgba_to_hsva(
+            cell.inside_color[0],
+            cell.inside_color[1],
+            cell.inside_color[2],
+            cell.inside_color[3]
+        );
+    } else if color_type == "membrane"{
+        (h, s, v, a) = rgba_to_hsva(
+            cell.membrane_color[0],
+            cell.membrane_color[1],
+            cell.membrane_color[2],
+            cell.membrane_color[3]
+        );
+    } else if color_type == "nucleus"{
+        (h, s, v, a) = rgba_to_hsva(
+            cell.nucleus_color[0],
+            cell.nucleus_color[1],
+            cell.nucleus_color[2],
+            cell.nucleus_color[3]
+        );
+    } else {
+        panic!("Invalid color_type: {}", color_type);
+    }
+
+    let terrain_val = env.sample_terrain(cell.x_pos, cell.y_pos);
+    let v_new = lowest_cell_brightness + (1.0 - lowest_cell_brightness) * terrain_val as f32;
+    let [r_new, b_new, g_new, a_new] = hsva_to_rgba(h, s, v_new, a);
+
+    [r_new, b_new, g_new, a_new]
+
+}
+
+fn f32_color_to_sdl_color(color: [f32; 4]) -> sdl2::pixels::Color {
+    let (r, g, b, _) = (
+        (color[0] * 255.0) as u8,
+        (color[1] * 255.0) as u8,
+        (color[2] * 255.0) as u8,
+        (color[3] * 255.0) as u8,
+    );
+    sdl2::pixels::Color::RGB(r, g, b)
+}
+
+
+pub fn handle_events(event_pump: &mut EventPump, ui_state: &mut UIState, env: &mut Environment) {
+    let pan_step = 40;
+
+    for event in event_pump.poll_iter() {
+        // While the console is open it takes all typing, Escape closes it instead of quitting
+        if ui_state.console.open {
+            match &event {
+                Event::TextInput { text, .. } => {
+                    ui_state.console.push_text(text);
+                    continue;
+                }
+                Event::KeyDown { keycode: Some(keycode), .. } => {
+                    match keycode {
+                        Keycode::Backquote | Keycode::Escape => ui_state.console.toggle(),
+                        Keycode::Return | Keycode::KpEnter => ui_state.console.submit(env),
+                        Keycode::Backspace => ui_state.console.backspace(),
+                        _ => {}
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        match event {
+            Event::Quit { .. }
+            | Event::KeyDown {
+                keycode: Some(sdl2::keyboard::Keycode::Escape),
+                ..
+            } => {
+                // Handle exit logic
+                ui_state.should_exit = true;
+            }
+            Event::MouseMotion { x, y, xrel, yrel, mousestate, .. } => {
+                let (x, y) = ui_state.to_pixels(x, y);
+                let (xrel, yrel) = ui_state.to_pixels(xrel, yrel);
+                ui_state.mouse_x = x;
+                ui_state.mouse_y = y;
+                let (world_x, world_y) = ui_state.camera.screen_to_world(x as f64, y as f64);
+                if ui_state.minimap_drag {
+                    center_on_minimap(ui_state, env, x, y);
+                } else if let Some(cell_id) = ui_state.tools.dragging_cell {
+                    env.move_cell(cell_id, world_x, world_y);
+                } else if ui_state.tools.tool == Tool::Brush && mousestate.left() {
+                    if let Some((last_x, last_y)) = ui_state.tools.last_brush_pos {
+                        // Space the dabs a brush radius apart so a slow drag doesn't flood the world
+                        if ((x - last_x).pow(2) + (y - last_y).pow(2)) as f64 >= (BRUSH_RADIUS as f64).powi(2) {
+                            brush_spawn(ui_state, env, x, y);
+                        }
+                    }
+                } else if ui_state.tools.tool == Tool::Terrain && (mousestate.left() || mousestate.right()) {
+                    terrain_dab(ui_state, env, x, y, mousestate.right());
+                } else if ui_state.tools.tool == Tool::Brush && mousestate.right() {
+                    env.kill_cells_in_radius(world_x, world_y, BRUSH_RADIUS as f64 / ui_state.camera.zoom);
+                } else if (mousestate.left() && ui_state.tools.tool == Tool::Inspect) || mousestate.middle() {
+                    ui_state.camera.pan_by_screen(xrel, yrel);
+                }
+            }
+            Event::MouseButtonDown { mouse_btn: MouseButton::Left, x, y, .. } => {
+                let (x, y) = ui_state.to_pixels(x, y);
+                let (world_x, world_y) = ui_state.camera.screen_to_world(x as f64, y as f64);
+                if ui_state.layout.in_minimap(x, y) {
+                    ui_state.minimap_drag = true;
+                    center_on_minimap(ui_state, env, x, y);
+                } else if ui_state.layout.in_world_view(x, y) {
+                    match ui_state.tools.tool {
+                        Tool::Inspect => ui_state.click_origin = Some((x, y)),
+                        Tool::Edit => match env.cell_at(world_x, world_y).map(|cell| cell.id) {
+                            Some(cell_id) => ui_state.tools.dragging_cell = Some(cell_id),
+                            None => {
+                                let step = env.step_stats.step;
+                                env.spawn_cell(world_x, world_y, ui_state.tools.genome_clipboard.as_ref(), step);
+                            }
+                        },
+                        Tool::Brush => brush_spawn(ui_state, env, x, y),
+                        Tool::Terrain => terrain_dab(ui_state, env, x, y, false),
+                    }
+                }
+            }
+            Event::MouseButtonDown { mouse_btn: MouseButton::Right, x, y, .. } => {
+                let (x, y) = ui_state.to_pixels(x, y);
+                let (world_x, world_y) = ui_state.camera.screen_to_world(x as f64, y as f64);
+                if ui_state.layout.in_world_view(x, y) {
+                    match ui_state.tools.tool {
+                        Tool::Inspect => {}
+                        Tool::Edit => {
+                            if let Some(cell_id) = env.cell_at(world_x, world_y).map(|cell| cell.id) {
+                                env.kill_cell(cell_id);
+                            }
+                        }
+                        Tool::Brush => {
+                            env.kill_cells_in_radius(world_x, world_y, BRUSH_RADIUS as f64 / ui_state.camera.zoom);
+                        }
+                        Tool::Terrain => terrain_dab(ui_state, env, x, y, true),
+                    }
+                }
+            }
+            Event::MouseButtonUp { mouse_btn: MouseButton::Left, .. } if ui_state.minimap_drag => {
+                ui_state.minimap_drag = false;
+            }
+            Event::MouseButtonUp { mouse_btn: MouseButton::Left, .. } if ui_state.tools.tool != Tool::Inspect => {
+                ui_state.tools.dragging_cell = None;
+                ui_state.tools.last_brush_pos = None;
+            }
+            Event::MouseButtonUp { mouse_btn: MouseButton::Left, x, y, .. } => {
+                let (x, y) = ui_state.to_pixels(x, y);
+                // A press and release without dragging is a click, which selects the cell under the cursor
+                if let Some((origin_x, origin_y)) = ui_state.click_origin.take() {
+                    if (x - origin_x).abs() <= 3 && (y - origin_y).abs() <= 3 {
+                        let (world_x, world_y) = ui_state.camera.screen_to_world(x as f64, y as f64);
+                        ui_state.selected_id = env.cell_at(world_x, world_y).map(|cell| cell.id);
+                        debug!("ui_util::handle_events >> selected_id: {:?}", ui_state.selected_id);
+                    }
+                }
+            }
+            // The layout and camera viewport follow the drawable size on the next frame
+            Event::Window { win_event: WindowEvent::SizeChanged(width, height), .. } => {
+                debug!("ui_util::handle_events >> Window resized to {}x{}", width, height);
+            }
+            Event::MouseWheel { y, .. } => {
+                ui_state.camera.zoom_at(ui_state.mouse_x as f64, ui_state.mouse_y as f64, y);
+            }
+            Event::KeyDown { keycode: Some(keycode), .. } => match keycode {
+                Keycode::F => {
+                    let (world_x, world_y) = ui_state.camera.screen_to_world(ui_state.mouse_x as f64, ui_state.mouse_y as f64);
+                    let cell_id = ui_state.selected_id.or_else(|| env.cell_at(world_x, world_y).map(|cell| cell.id));
+                    ui_state.camera.toggle_follow(cell_id);
+                }
+                Keycode::H => ui_state.hud.visible = !ui_state.hud.visible,
+                Keycode::P => {
+                    if let Some(cell) = selected_cell(env, ui_state.selected_id) {
+                        cell.print_cell_properties();
+                    }
+                }
+                Keycode::Home => ui_state.camera.fit_world(env.width(), env.height()),
+                Keycode::Left => ui_state.camera.pan_by_screen(pan_step, 0),
+                Keycode::Right => ui_state.camera.pan_by_screen(-pan_step, 0),
+                Keycode::Up => ui_state.camera.pan_by_screen(0, pan_step),
+                Keycode::Down => ui_state.camera.pan_by_screen(0, -pan_step),
+                Keycode::Space => ui_state.sim_control.toggle_pause(),
+                Keycode::N | Keycode::Period => ui_state.sim_control.request_step(),
+                Keycode::Equals | Keycode::KpPlus => ui_state.sim_control.speed_up(),
+                Keycode::Minus | Keycode::KpMinus => ui_state.sim_control.slow_down(),
+                Keycode::Backspace => ui_state.sim_control.reset_speed(),
+                Keycode::Tab => ui_state.sim_control.toggle_fast_forward(),
+                Keycode::S => match export_svg_snapshot(env) {
+                    Ok(path) => info!("handle_events >> Saved SVG snapshot to {}", path),
+                    Err(e) => error!("Failed to save SVG snapshot: {}", e),
+                },
+                Keycode::C => ui_state.overlays.cell_color = ui_state.overlays.cell_color.next(),
+                Keycode::O => ui_state.overlays.terrain = ui_state.overlays.terrain.next(),
+                Keycode::V => ui_state.overlays.velocity_vectors = !ui_state.overlays.velocity_vectors,
+                Keycode::X => ui_state.overlays.contacts = !ui_state.overlays.contacts,
+                Keycode::T => ui_state.trails.toggle(),
+                Keycode::Num1 => ui_state.tools.set_tool(Tool::Inspect),
+                Keycode::Num2 => ui_state.tools.set_tool(Tool::Edit),
+                Keycode::Num3 => ui_state.tools.set_tool(Tool::Brush),
+                Keycode::Num4 => ui_state.tools.select_terrain_tool(),
+                Keycode::Y => {
+                    ui_state.tools.genome_clipboard = selected_cell(env, ui_state.selected_id).map(|cell| cell.genome());
+                    debug!("ui_util::handle_events >> genome_clipboard: {:?}", ui_state.tools.genome_clipboard);
+                }
+                Keycode::U => ui_state.tools.genome_clipboard = None,
+                Keycode::M => ui_state.show_minimap = !ui_state.show_minimap,
+                Keycode::G => ui_state.show_side_panel = !ui_state.show_side_panel,
+                Keycode::Backquote => ui_state.console.toggle(),
+                // Only the HUD is drawn while rendering is off, so the simulation gets the whole frame
+                Keycode::D => ui_state.should_render = !ui_state.should_render,
+                Keycode::R => {
+                    ui_state.recording = !ui_state.recording;
+                    info!("handle_events >> recording: {}", ui_state.recording);
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+fn brush_spawn(ui_state: &mut UIState, env: &mut Environment, x: i32, y: i32) {
+    let (world_x, world_y) = ui_state.camera.screen_to_world(x as f64, y as f64);
+    let radius = BRUSH_RADIUS as f64 / ui_state.camera.zoom;
+    let step = env.step_stats.step;
+    env.spawn_cluster(world_x, world_y, radius, BRUSH_CELLS_PER_DAB, ui_state.tools.genome_clipboard.as_ref(), step);
+    ui_state.tools.last_brush_pos = Some((x, y));
+}
+
+fn terrain_dab(ui_state: &UIState, env: &mut Environment, x: i32, y: i32, right_button: bool) {
+    let (world_x, world_y) = ui_state.camera.screen_to_world(x as f64, y as f64);
+    let radius = BRUSH_RADIUS as f64 / ui_state.camera.zoom;
+    env.paint_terrain(world_x, world_y, radius, TERRAIN_BRUSH_STRENGTH, ui_state.tools.terrain_mode_for(right_button));
+}
+
+fn center_on_minimap(ui_state: &mut UIState, env: &Environment, x: i32, y: i32) {
+    if let Some(rect) = ui_state.layout.minimap {
+        let (world_x, world_y) = minimap_to_world(rect, env, x, y);
+        ui_state.camera.center_x = world_x;
+        ui_state.camera.center_y = world_y;
+        ui_state.camera.follow_id = None;
+    }
+}
+
+pub fn hsva_to_rgba(h: f32, s: f32, v: f32, a: f32) -> [u8; 4] {
+    let normalized_h = (h % 1.0 + 1.0) % 1.0;
+    let c = v * s;
+    let x = c * (1.0 - ((normalized_h * 6.0) % 2.0 - 1.0).abs());
+    let m = v - c;
+
+    let (r, g, b) = if normalized_h >= 0.0 && normalized_h < 1.0/6.0 {
+        (c, x, 0.0)
+    } else if normalized_h >= 1.0/6.0 && normalized_h < 2.0/6.0 {
+        (x, c, 0.0)
+    } else if normalized_h >= 2.0/6.0 && normalized_h < 3.0/6.0 {
+        (0.0, c, x)
+    } else if normalized_h >= 3.0/6.0 && normalized_h < 4.0/6.0 {
+        (0.0, x, c)
+    } else if normalized_h >= 4.0/6.0 && normalized_h < 5.0/6.0 {
+        (x, 0.0, c)
+    } else {
+        (c, 0.0, x)
+    };
+
+    [
+        ((r + m) * 255.0) as u8,
+        ((g + m) * 255.0) as u8,
+        ((b + m) * 255.0) as u8,
+        (a * 255.0) as u8,
+    ]
+}
+
+pub fn rgba_to_hsva(r: u8, g: u8, b: u8, a: u8) -> (f32, f32, f32, f32) {
+    let r = r as f32 / 255.0;
+    let g = g as f32 / 255.0;
+    let b = b as f32 / 255.0;
+    
+    let max = r.max(g.max(b));
+    let min = r.min(g.min(b));
+    let delta = max - min;
+
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        ((1.0/6.0 * ((g - b) / delta) + 1.0) % 1.0)
+    } else if max == g {
+        (1.0/6.0 * ((b - r) / delta) + 1.0/3.0)
+    } else {
+        (1.0/6.0 * ((r - g) / delta) + 2.0/3.0)
+    };
+
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+    let v = max;
+    let a = a as f32 / 255.0;
+
+    (h, s, v, a)
+}
+
+pub fn generate_loud_tone() -> Vec<f32> {
+    let num_samples = (44100 as f32 * 1.0) as usize;
+    let mut amplitude_sequence = vec![0.0; num_samples];
+
+    for i in 0..num_samples {
+        let t = i as f32 / 44100 as f32;  // time in seconds
+        amplitude_sequence[i] = 0.9 * f32::sin(2.0 * std::f32::consts::PI * 440.0 * t);  // 0.9 to make it loud but not clipping
+    }
+
+    amplitude_sequence
+}
@@ -0,0 +1,272 @@
+use log::{debug, error, info, trace, warn, LevelFilter};
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Canvas};
+use sdl2::video::Window;
+
+use crate::cell::Cell;
+use crate::constants::{OVERLAY_BLOCK_SIZE, OVERLAY_ALPHA, OVERLAY_VELOCITY_SCALE};
+use crate::environment::Environment;
+use crate::utils::grid::Grid;
+use crate::utils::ui_util::camera::Camera;
+use crate::utils::ui_util::hsva_to_rgba;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CellColorMode {
+    Natural,
+    Energy,
+    Health,
+    Age,
+    ReproductionCost,
+    Lineage,
+    GenomeCluster,
+}
+
+impl CellColorMode {
+    pub fn next(self) -> Self {
+        match self {
+            CellColorMode::Natural => CellColorMode::Energy,
+            CellColorMode::Energy => CellColorMode::Health,
+            CellColorMode::Health => CellColorMode::Age,
+            CellColorMode::Age => CellColorMode::ReproductionCost,
+            CellColorMode::ReproductionCost => CellColorMode::Lineage,
+            CellColorMode::Lineage => CellColorMode::GenomeCluster,
+            CellColorMode::GenomeCluster => CellColorMode::Natural,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CellColorMode::Natural => "natural",
+            CellColorMode::Energy => "energy",
+            CellColorMode::Health => "health",
+            CellColorMode::Age => "age",
+            CellColorMode::ReproductionCost => "repro cost",
+            CellColorMode::Lineage => "lineage",
+            CellColorMode::GenomeCluster => "genome cluster",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TerrainOverlay {
+    None,
+    GradientMagnitude,
+    // Light is what the cells feed on, so this doubles as the nutrient map
+    Light,
+    CellDensity,
+}
+
+impl TerrainOverlay {
+    pub fn next(self) -> Self {
+        match self {
+            TerrainOverlay::None => TerrainOverlay::GradientMagnitude,
+            TerrainOverlay::GradientMagnitude => TerrainOverlay::Light,
+            TerrainOverlay::Light => TerrainOverlay::CellDensity,
+            TerrainOverlay::CellDensity => TerrainOverlay::None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TerrainOverlay::None => "none",
+            TerrainOverlay::GradientMagnitude => "gradient",
+            TerrainOverlay::Light => "light / nutrients",
+            TerrainOverlay::CellDensity => "cell density",
+        }
+    }
+}
+
+// Which data views are layered over the normal picture
+pub struct Overlays {
+    pub cell_color: CellColorMode,
+    pub terrain: TerrainOverlay,
+    pub velocity_vectors: bool,
+    pub contacts: bool,
+}
+
+impl Overlays {
+    pub fn new() -> Self {
+        Self {
+            cell_color: CellColorMode::Natural,
+            terrain: TerrainOverlay::None,
+            velocity_vectors: false,
+            contacts: false,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        format!("cells: {}  terrain: {}", self.cell_color.name(), self.terrain.name())
+    }
+}
+
+// Population ranges used to normalise the per-cell colour scales, computed once a frame
+pub struct CellColorScale {
+    max_age: f64,
+    min_reproduction_cost: f64,
+    max_reproduction_cost: f64,
+}
+
+impl CellColorScale {
+    pub fn from_environment(env: &Environment) -> Self {
+        let mut scale = Self { max_age: 1.0, min_reproduction_cost: f64::MAX, max_reproduction_cost: f64::MIN };
+        for cell in env.cells.iter() {
+            scale.max_age = scale.max_age.max(cell.age as f64);
+            scale.min_reproduction_cost = scale.min_reproduction_cost.min(cell.reproduction_cost);
+            scale.max_reproduction_cost = scale.max_reproduction_cost.max(cell.reproduction_cost);
+        }
+        scale
+    }
+}
+
+// None means draw the cell with its own genome colours
+pub fn cell_overlay_color(cell: &Cell, mode: CellColorMode, scale: &CellColorScale) -> Option<[u8; 4]> {
+    let normalize = |val: f64, min: f64, max: f64| if max - min > f64::EPSILON { (val - min) / (max - min) } else { 0.5 };
+    match mode {
+        CellColorMode::Natural => None,
+        CellColorMode::Energy => Some(heat_color(normalize(cell.energy, 0.0, cell.energy_capacity))),
+        CellColorMode::Health => Some(heat_color(normalize(cell.health, 0.0, cell.health_capacity))),
+        CellColorMode::Age => Some(heat_color(normalize(cell.age as f64, 0.0, scale.max_age))),
+        CellColorMode::ReproductionCost => Some(heat_color(normalize(
+            cell.reproduction_cost,
+            scale.min_reproduction_cost,
+            scale.max_reproduction_cost,
+        ))),
+        CellColorMode::Lineage => Some(id_color(cell.lineage_id as u64)),
+        CellColorMode::GenomeCluster => Some(id_color(genome_cluster(cell))),
+    }
+}
+
+// Cells whose genomes fall in the same coarse bucket share a cluster id: membrane hue in
+// 12 steps, the other two colours' hues in 4 and reproduction cost in 8
+pub fn genome_cluster(cell: &Cell) -> u64 {
+    let genome = cell.genome();
+    let hue_bucket = |color: [u8; 4], buckets: f32| {
+        let (h, _, _, _) = crate::utils::ui_util::rgba_to_hsva(color[0], color[1], color[2], color[3]);
+        ((h.rem_euclid(1.0) * buckets) as u64).min(buckets as u64 - 1)
+    };
+    let cost_bucket = ((genome.reproduction_cost / 25.0).max(0.0) as u64).min(7);
+    ((hue_bucket(genome.membrane_color, 12.0) * 4 + hue_bucket(genome.inside_color, 4.0)) * 4
+        + hue_bucket(genome.nucleus_color, 4.0)) * 8
+        + cost_bucket
+}
+
+// Spreads consecutive ids around the hue wheel so neighbours stay distinguishable
+pub fn id_color(id: u64) -> [u8; 4] {
+    let hue = (id as f64 * 0.618_033_988_75).fract() as f32;
+    hsva_to_rgba(hue, 0.75, 0.95, 1.0)
+}
+
+// Blue through green and yellow to red for t in [0, 1]
+pub fn heat_color(t: f64) -> [u8; 4] {
+    let t = t.clamp(0.0, 1.0) as f32;
+    hsva_to_rgba((1.0 - t) * (240.0 / 360.0), 0.85, 0.95, 1.0)
+}
+
+// World grid of cells per block, for the density heatmap
+pub fn cell_density(env: &Environment, block_size: usize) -> Grid<f64> {
+    let mut density = Grid::new(env.width().div_ceil(block_size), env.height().div_ceil(block_size), 0.0);
+    for cell in env.cells.iter().filter(|cell| cell.alive) {
+        let bx = (cell.x_pos.max(0.0) as usize / block_size).min(density.width() - 1);
+        let by = (cell.y_pos.max(0.0) as usize / block_size).min(density.height() - 1);
+        density[(bx, by)] += 1.0;
+    }
+    density
+}
+
+// Coarse translucent blocks over the visible part of the terrain
+pub fn render_terrain_overlay(
+    env: &Environment,
+    overlay: TerrainOverlay,
+    camera: &Camera,
+    canvas: &mut Canvas<Window>,
+) -> Result<(), String> {
+    if overlay == TerrainOverlay::None {
+        return Ok(());
+    }
+    let block_size = OVERLAY_BLOCK_SIZE.max(1);
+    let density = if overlay == TerrainOverlay::CellDensity { Some(cell_density(env, block_size)) } else { None };
+    let max_density = density.as_ref().map(|grid| grid.iter().cloned().fold(1.0, f64::max)).unwrap_or(1.0);
+    let max_gradient = if overlay == TerrainOverlay::GradientMagnitude {
+        env.gradient.iter().map(|(dx, dy)| (dx * dx + dy * dy).sqrt()).fold(f64::EPSILON, f64::max)
+    } else {
+        1.0
+    };
+
+    let (x_min, y_min, x_max, y_max) = camera.visible_world_rect();
+    let bx_min = (x_min.max(0.0) as usize) / block_size;
+    let by_min = (y_min.max(0.0) as usize) / block_size;
+    let bx_max = ((x_max.max(0.0) as usize) / block_size).min((env.width() - 1) / block_size);
+    let by_max = ((y_max.max(0.0) as usize) / block_size).min((env.height() - 1) / block_size);
+
+    canvas.set_blend_mode(BlendMode::Blend);
+    for by in by_min..=by_max {
+        for bx in bx_min..=bx_max {
+            let center_x = ((bx * block_size + block_size / 2) as f64).min(env.width() as f64 - 1.0);
+            let center_y = ((by * block_size + block_size / 2) as f64).min(env.height() as f64 - 1.0);
+            let t = match overlay {
+                TerrainOverlay::GradientMagnitude => {
+                    let (dx, dy) = env.sample_gradient(center_x, center_y);
+                    (dx * dx + dy * dy).sqrt() / max_gradient
+                }
+                TerrainOverlay::Light => {
+                    let light = env.sample_terrain(center_x, center_y);
+                    match env.obstacles.as_ref() {
+                        Some(obstacles) => light * obstacles.light_at(center_x, center_y),
+                        None => light,
+                    }
+                }
+                TerrainOverlay::CellDensity => density.as_ref().unwrap()[(bx, by)] / max_density,
+                TerrainOverlay::None => unreachable!(),
+            };
+            let [r, g, b, _] = heat_color(t);
+            let (sx1, sy1) = camera.world_to_screen((bx * block_size) as f64, (by * block_size) as f64);
+            let (sx2, sy2) = camera.world_to_screen(((bx + 1) * block_size) as f64, ((by + 1) * block_size) as f64);
+            canvas.set_draw_color(Color::RGBA(r, g, b, OVERLAY_ALPHA));
+            canvas.fill_rect(Rect::new(
+                sx1.floor() as i32,
+                sy1.floor() as i32,
+                (sx2.ceil() - sx1.floor()).max(1.0) as u32,
+                (sy2.ceil() - sy1.floor()).max(1.0) as u32,
+            ))?;
+        }
+    }
+    canvas.set_blend_mode(BlendMode::None);
+    Ok(())
+}
+
+pub fn render_velocity_vectors(env: &Environment, camera: &Camera, canvas: &mut Canvas<Window>) -> Result<(), String> {
+    for cell in env.cells.iter().filter(|cell| cell.alive && camera.is_visible(cell.x_pos, cell.y_pos, cell.radius)) {
+        let (x1, y1) = camera.world_to_screen(cell.x_pos, cell.y_pos);
+        let (x2, y2) = camera.world_to_screen(
+            cell.x_pos + cell.x_vel * OVERLAY_VELOCITY_SCALE,
+            cell.y_pos + cell.y_vel * OVERLAY_VELOCITY_SCALE,
+        );
+        canvas.aa_line(x1 as i16, y1 as i16, x2 as i16, y2 as i16, Color::RGBA(255, 255, 255, 220))?;
+        canvas.filled_circle(x2 as i16, y2 as i16, 2, Color::RGBA(255, 255, 255, 220))?;
+    }
+    Ok(())
+}
+
+// A line between the centres of every pair of touching cells
+pub fn render_contacts(env: &Environment, camera: &Camera, canvas: &mut Canvas<Window>) -> Result<(), String> {
+    let cells: Vec<&Cell> = env.cells.iter().filter(|cell| cell.alive).collect();
+    for (i, cell1) in cells.iter().enumerate() {
+        for cell2 in cells[i + 1..].iter() {
+            let dx = cell2.x_pos - cell1.x_pos;
+            let dy = cell2.y_pos - cell1.y_pos;
+            let touch_dist = cell1.radius + cell2.radius;
+            if dx * dx + dy * dy >= touch_dist * touch_dist {
+                continue;
+            }
+            if !camera.is_visible(cell1.x_pos, cell1.y_pos, touch_dist) {
+                continue;
+            }
+            let (x1, y1) = camera.world_to_screen(cell1.x_pos, cell1.y_pos);
+            let (x2, y2) = camera.world_to_screen(cell2.x_pos, cell2.y_pos);
+            canvas.aa_line(x1 as i16, y1 as i16, x2 as i16, y2 as i16, Color::RGBA(255, 80, 60, 230))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+use crate::cell::Genome;
+use crate::environment::TerrainBrushMode;
+use crate::constants::BRUSH_RADIUS;
+
+// What the left and right mouse buttons do in the world view
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tool {
+    // Left click selects, left drag pans
+    Inspect,
+    // Left click spawns, left drag on a cell moves it, right click kills
+    Edit,
+    // Left drag sprays clusters of cells, right drag kills everything under the brush
+    Brush,
+    // Left drag paints the terrain in the current brush mode, right drag does the opposite
+    Terrain,
+}
+
+impl Tool {
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Inspect => "inspect",
+            Tool::Edit => "edit",
+            Tool::Brush => "brush",
+            Tool::Terrain => "terrain",
+        }
+    }
+}
+
+pub struct ToolState {
+    pub tool: Tool,
+    // Genome copied from a cell, used for spawning instead of a random one
+    pub genome_clipboard: Option<Genome>,
+    pub dragging_cell: Option<i64>,
+    // Screen position of the last brush dab, so dragging spaces them out
+    pub last_brush_pos: Option<(i32, i32)>,
+    pub terrain_mode: TerrainBrushMode,
+}
+
+impl ToolState {
+    pub fn new() -> Self {
+        Self {
+            tool: Tool::Inspect,
+            genome_clipboard: None,
+            dragging_cell: None,
+            last_brush_pos: None,
+            terrain_mode: TerrainBrushMode::Raise,
+        }
+    }
+
+    // Selecting the terrain tool again steps through its modes
+    pub fn select_terrain_tool(&mut self) {
+        if self.tool == Tool::Terrain {
+            self.terrain_mode = match self.terrain_mode {
+                TerrainBrushMode::Raise => TerrainBrushMode::Lower,
+                TerrainBrushMode::Lower => TerrainBrushMode::Smooth,
+                TerrainBrushMode::Smooth => TerrainBrushMode::Raise,
+            };
+        } else {
+            self.set_tool(Tool::Terrain);
+        }
+    }
+
+    // The right button inverts raise and lower; smoothing has no opposite
+    pub fn terrain_mode_for(&self, right_button: bool) -> TerrainBrushMode {
+        match (self.terrain_mode, right_button) {
+            (TerrainBrushMode::Raise, true) => TerrainBrushMode::Lower,
+            (TerrainBrushMode::Lower, true) => TerrainBrushMode::Raise,
+            (mode, _) => mode,
+        }
+    }
+
+    pub fn set_tool(&mut self, tool: Tool) {
+        self.tool = tool;
+        self.dragging_cell = None;
+        self.last_brush_pos = None;
+    }
+
+    pub fn label(&self) -> String {
+        let tool = if self.tool == Tool::Terrain {
+            format!("terrain ({:?})", self.terrain_mode).to_lowercase()
+        } else {
+            self.tool.name().to_string()
+        };
+        format!("tool: {}  genome: {}", tool, if self.genome_clipboard.is_some() { "copied" } else { "random" })
+    }
+}
+
+pub fn render_brush_cursor(mouse_x: i32, mouse_y: i32, canvas: &mut Canvas<Window>) -> Result<(), String> {
+    canvas.aa_circle(mouse_x as i16, mouse_y as i16, BRUSH_RADIUS as i16, Color::RGBA(255, 255, 255, 180))?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::pixels::Color;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+use crate::constants::{TRAIL_LENGTH, TRAIL_SAMPLE_EVERY, TRAILS_VISIBLE};
+use crate::environment::Environment;
+use crate::utils::ui_util::camera::Camera;
+use crate::utils::ui_util::overlay::id_color;
+
+struct Trail {
+    lineage_id: i64,
+    points: VecDeque<(f64, f64)>,
+}
+
+// Recent positions of every living cell, kept in a fixed size ring buffer per cell
+pub struct Trails {
+    pub enabled: bool,
+    trails: HashMap<i64, Trail>,
+}
+
+impl Trails {
+    pub fn new() -> Self {
+        Self {
+            enabled: TRAILS_VISIBLE,
+            trails: HashMap::new(),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        // Start fresh so turning trails back on doesn't join up stale positions
+        self.trails.clear();
+    }
+
+    pub fn record(&mut self, env: &Environment) {
+        if !self.enabled || env.step_stats.step % TRAIL_SAMPLE_EVERY.max(1) != 0 {
+            return;
+        }
+        for cell in env.cells.iter().filter(|cell| cell.alive) {
+            let trail = self.trails.entry(cell.id).or_insert_with(|| Trail {
+                lineage_id: cell.lineage_id,
+                points: VecDeque::with_capacity(TRAIL_LENGTH),
+            });
+            if trail.points.len() == TRAIL_LENGTH {
+                trail.points.pop_front();
+            }
+            trail.points.push_back((cell.x_pos, cell.y_pos));
+        }
+        // Dead cells' trails go with them
+        let alive: HashSet<i64> = env.cells.iter().filter(|cell| cell.alive).map(|cell| cell.id).collect();
+        self.trails.retain(|id, _| alive.contains(id));
+    }
+
+    // Every segment in world coordinates, coloured by lineage and fading from transparent
+    // at its oldest point to solid at the cell. Shared by the window and software renderers.
+    pub fn segments(&self) -> impl Iterator<Item = ((f64, f64), (f64, f64), [u8; 4])> + '_ {
+        self.trails.values().flat_map(|trail| {
+            let [r, g, b, _] = id_color(trail.lineage_id as u64);
+            let num_points = trail.points.len();
+            trail.points.iter().zip(trail.points.iter().skip(1)).enumerate().map(move |(i, (p1, p2))| {
+                let alpha = (220.0 * (i + 1) as f64 / num_points as f64) as u8;
+                (*p1, *p2, [r, g, b, alpha])
+            })
+        })
+    }
+}
+
+pub fn render_trails(trails: &Trails, camera: &Camera, canvas: &mut Canvas<Window>) -> Result<(), String> {
+    if !trails.enabled {
+        return Ok(());
+    }
+    let (x_min, y_min, x_max, y_max) = camera.visible_world_rect();
+    let on_screen = |(x, y): &(f64, f64)| *x >= x_min && *x <= x_max && *y >= y_min && *y <= y_max;
+    for (p1, p2, [r, g, b, a]) in trails.segments() {
+        if !on_screen(&p1) && !on_screen(&p2) {
+            continue;
+        }
+        let (x1, y1) = camera.world_to_screen(p1.0, p1.1);
+        let (x2, y2) = camera.world_to_screen(p2.0, p2.1);
+        canvas.aa_line(x1 as i16, y1 as i16, x2 as i16, y2 as i16, Color::RGBA(r, g, b, a))?;
+    }
+    Ok(())
+}
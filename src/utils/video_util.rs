@@ -0,0 +1,147 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+use image::imageops::FilterType;
+use image::RgbaImage;
+use log::{debug, error, info, trace, warn, LevelFilter};
+
+enum VideoSink {
+    // Uncompressed YUV4MPEG2, readable by ffmpeg, mpv and most editors
+    Y4m(BufWriter<File>),
+    // Raw RGBA frames piped into a local ffmpeg process
+    Ffmpeg { child: Child, stdin: BufWriter<ChildStdin> },
+}
+
+// Streams frames to disk as they are produced instead of saving one PNG per frame.
+// Frames can come from the window or from any other renderer that fills an RgbaImage.
+pub struct VideoRecorder {
+    sink: Option<VideoSink>,
+    pub path: String,
+    width: u32,
+    height: u32,
+    frames_written: u64,
+}
+
+impl VideoRecorder {
+    // A width or height of 0 takes the size of the first frame instead
+    pub fn new(output_dir: &str, width: u32, height: u32, fps: u32, use_ffmpeg: bool, first_frame: &RgbaImage) -> Result<Self, String> {
+        std::fs::create_dir_all(output_dir).map_err(|e| e.to_string())?;
+        let width = if width == 0 { first_frame.width() } else { width };
+        let height = if height == 0 { first_frame.height() } else { height };
+        // Chroma subsampling in most codecs needs even dimensions
+        let (width, height) = ((width & !1).max(2), (height & !1).max(2));
+        let timestamp = chrono::Local::now().format("%Y%m%d%H%M%S");
+
+        let (sink, path) = if use_ffmpeg && ffmpeg_available() {
+            let path = Path::new(output_dir).join(format!("recording_{}.mp4", timestamp)).to_string_lossy().into_owned();
+            let mut child = Command::new("ffmpeg")
+                .args(["-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba"])
+                .args(["-s", &format!("{}x{}", width, height), "-framerate", &fps.to_string(), "-i", "-"])
+                .args(["-c:v", "libx264", "-preset", "medium", "-crf", "20", "-pix_fmt", "yuv420p", &path])
+                .stdin(Stdio::piped())
+                .spawn()
+                .map_err(|e| e.to_string())?;
+            let stdin = child.stdin.take().ok_or_else(|| "ffmpeg stdin unavailable".to_string())?;
+            (VideoSink::Ffmpeg { child, stdin: BufWriter::new(stdin) }, path)
+        } else {
+            if use_ffmpeg {
+                warn!("VideoRecorder::new >> ffmpeg not found, falling back to Y4M");
+            }
+            let path = Path::new(output_dir).join(format!("recording_{}.y4m", timestamp)).to_string_lossy().into_owned();
+            let mut writer = BufWriter::new(File::create(&path).map_err(|e| e.to_string())?);
+            write!(writer, "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444\n", width, height, fps).map_err(|e| e.to_string())?;
+            (VideoSink::Y4m(writer), path)
+        };
+        info!("VideoRecorder::new >> Recording {}x{} at {} fps to {}", width, height, fps, path);
+
+        Ok(Self {
+            sink: Some(sink),
+            path,
+            width,
+            height,
+            frames_written: 0,
+        })
+    }
+
+    pub fn frames_written(&self) -> u64 {
+        self.frames_written
+    }
+
+    pub fn write_frame(&mut self, frame: &RgbaImage) -> Result<(), String> {
+        let resized;
+        let frame = if frame.width() != self.width || frame.height() != self.height {
+            resized = image::imageops::resize(frame, self.width, self.height, FilterType::Triangle);
+            &resized
+        } else {
+            frame
+        };
+
+        match self.sink.as_mut() {
+            Some(VideoSink::Y4m(writer)) => {
+                writer.write_all(b"FRAME\n").map_err(|e| e.to_string())?;
+                writer.write_all(&rgba_to_yuv444_planes(frame)).map_err(|e| e.to_string())?;
+            }
+            Some(VideoSink::Ffmpeg { stdin, .. }) => {
+                stdin.write_all(frame.as_raw()).map_err(|e| e.to_string())?;
+            }
+            None => return Err("Recorder already finished".to_string()),
+        }
+        self.frames_written += 1;
+        trace!("VideoRecorder::write_frame >> frame {}", self.frames_written);
+        Ok(())
+    }
+
+    // Flushes the file, or closes the pipe and waits for ffmpeg to finish encoding
+    pub fn finish(&mut self) -> Result<(), String> {
+        match self.sink.take() {
+            Some(VideoSink::Y4m(mut writer)) => writer.flush().map_err(|e| e.to_string())?,
+            Some(VideoSink::Ffmpeg { mut child, mut stdin }) => {
+                stdin.flush().map_err(|e| e.to_string())?;
+                drop(stdin);
+                let status = child.wait().map_err(|e| e.to_string())?;
+                if !status.success() {
+                    return Err(format!("ffmpeg exited with {}", status));
+                }
+            }
+            None => return Ok(()),
+        }
+        info!("VideoRecorder::finish >> Wrote {} frames to {}", self.frames_written, self.path);
+        Ok(())
+    }
+}
+
+impl Drop for VideoRecorder {
+    fn drop(&mut self) {
+        self.finish().unwrap_or_else(|e| {
+            error!("Failed to finish recording {}: {}", self.path, e);
+        });
+    }
+}
+
+pub fn ffmpeg_available() -> bool {
+    Command::new("ffmpeg")
+        .arg("-version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+// BT.601 studio swing, full resolution chroma, planes laid out Y then U then V
+fn rgba_to_yuv444_planes(frame: &RgbaImage) -> Vec<u8> {
+    let num_pixels = (frame.width() * frame.height()) as usize;
+    let mut planes = vec![0u8; num_pixels * 3];
+    for (i, pixel) in frame.as_raw().chunks_exact(4).enumerate() {
+        let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+        let y = 16.0 + (65.481 * r + 128.553 * g + 24.966 * b) / 255.0;
+        let u = 128.0 + (-37.797 * r - 74.203 * g + 112.0 * b) / 255.0;
+        let v = 128.0 + (112.0 * r - 93.786 * g - 18.214 * b) / 255.0;
+        planes[i] = y.round() as u8;
+        planes[num_pixels + i] = u.round() as u8;
+        planes[num_pixels * 2 + i] = v.round() as u8;
+    }
+    planes
+}